@@ -0,0 +1,19 @@
+import init, { collage_worker_handle } from './spotify_egui.js';
+
+// Analyses and draws collages off the main thread; see src/image_processing/worker.rs for the messages.
+// Messages that arrive while the module is still loading wait for it
+const pending = [];
+let ready = false;
+
+self.onmessage = (event) => {
+    if (ready) {
+        collage_worker_handle(event.data);
+    } else {
+        pending.push(event.data);
+    }
+};
+
+init().then(() => {
+    ready = true;
+    pending.splice(0).forEach((message) => collage_worker_handle(message));
+});
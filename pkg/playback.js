@@ -1,4 +1,4 @@
-import { set_sdk_status } from './spotify_egui.js';
+import { set_sdk_status, sync_media_session } from './spotify_egui.js';
 import init, * as wasm from './spotify_egui.js';
 
 // Initialize wasm
@@ -150,6 +150,8 @@ function initializePlayer() {
             window.totalDuration = state.duration;
             window.isPlaying = !state.paused;
             window.shuffleState = state.shuffle;
+            // Publish track metadata and position to the OS media controls
+            sync_media_session();
             // Reset the update timer since we just got fresh state
             lastUpdateTime = Date.now();
             lastTickTime = Date.now(); // Reset tick timer on state change
@@ -1,7 +1,8 @@
-use super::models::SavedTracksResponse;
+use super::models::{SavedTracksResponse, TrackInfo};
 use crate::utils::log_error;
-use crate::ui::APP_STATE;
-use crate::storage::{load_tracks, save_tracks};
+use crate::ui::{TrackList, APP_STATE};
+use crate::api_request::library::cached_library;
+use crate::storage::save_tracks;
 use reqwest::Client;
 
 // Fetches the user's saved tracks from Spotify and updates the app state
@@ -17,13 +18,13 @@ pub async fn fetch_saved_tracks(token: String) {
     gloo_timers::future::TimeoutFuture::new(100).await;
 
     // Try to load from storage first
-    if let Some(stored_tracks) = load_tracks() {
+    if let Some(stored_tracks) = cached_library() {
         let mut state = APP_STATE.lock().unwrap();
         let initial_load = state.tracks_per_load;
         state.total_tracks = Some(stored_tracks.total);
         
         // Keep all tracks in storage but only load initial batch into state
-        state.saved_tracks = stored_tracks.tracks[..initial_load as usize].to_vec();
+        state.saved_tracks = TrackList::new(stored_tracks.tracks[..initial_load as usize].to_vec());
         state.loaded_tracks_count = state.saved_tracks.len() as i32;
         state.is_loading = false;
         
@@ -31,6 +32,7 @@ pub async fn fetch_saved_tracks(token: String) {
         if let Err(e) = save_tracks(&stored_tracks.tracks, stored_tracks.total) {
             log_error(&format!("Failed to save tracks to storage: {}", e));
         }
+        state.unsaved_library = None;
         return;
     }
 
@@ -63,7 +65,7 @@ async fn fetch_tracks_batch(client: &Client, token: &str, offset: usize, limit:
 
 pub async fn load_more_tracks(token: String, is_initial: bool) {
     // Try loading from storage first
-    if let Some(stored_tracks) = load_tracks() {
+    if let Some(stored_tracks) = cached_library() {
         let mut state = APP_STATE.lock().unwrap();  // Changed back to mut
         let offset = state.loaded_tracks_count as usize;
         let desired_limit = if state.tracks_per_load >= 1000 {
@@ -82,12 +84,13 @@ pub async fn load_more_tracks(token: String, is_initial: bool) {
         
         if !next_batch.is_empty() {
             let batch_len = next_batch.len();
-            state.saved_tracks.extend(next_batch);
+            state.saved_tracks.make_mut().extend(next_batch);
             
             // Save all tracks back to storage in their original order
             if let Err(e) = save_tracks(&stored_tracks.tracks, stored_tracks.total) {
                 log_error(&format!("Failed to save tracks to storage: {}", e));
             }
+            state.unsaved_library = None;
             state.loaded_tracks_count += batch_len as i32;
             state.is_loading = false;
             return;
@@ -129,7 +132,7 @@ pub async fn load_more_tracks(token: String, is_initial: bool) {
         if let Some(tracks) = fetch_tracks_batch(&client, &token, current_offset, current_limit).await {
             let items_len = tracks.items.len();
             // Process tracks in order (newest first)
-            let track_info: Vec<(String, String, String, String)> = tracks.items
+            let track_info: Vec<TrackInfo> = tracks.items
                 .into_iter()
                 .map(|item| {
                     let artists = item.track.artists
@@ -144,20 +147,34 @@ pub async fn load_more_tracks(token: String, is_initial: bool) {
                         .map(|img| img.url.clone())
                         .unwrap_or_default();
 
-                    (item.track.name, artists, image_url, item.track.uri)
+                    TrackInfo {
+                        name: item.track.name,
+                        artists,
+                        image_url,
+                        uri: item.track.uri,
+                        album: item.track.album.name.unwrap_or_default(),
+                        release_date: item.track.album.release_date.unwrap_or_default(),
+                        explicit: item.track.explicit,
+                        duration_ms: item.track.duration_ms,
+                        added_at: item.added_at,
+                        popularity: item.track.popularity,
+                        album_uri: item.track.album.uri,
+                        artist_uri: item.track.artists.into_iter().next().map(|artist| artist.uri).unwrap_or_default(),
+                    }
                 })
                 .collect();
             
             let total = tracks.total;
             let mut state = APP_STATE.lock().unwrap();
             state.total_tracks = Some(total);
-            state.saved_tracks.extend(track_info.into_iter());
+            state.saved_tracks.make_mut().extend(track_info.into_iter());
             state.loaded_tracks_count += items_len as i32;
             
             if state.loaded_tracks_count >= total {
                 if let Err(e) = save_tracks(&state.saved_tracks, total) {
                     log_error(&format!("Failed to save tracks to storage: {}", e));
                 }
+                state.unsaved_library = None;
                 state.is_loading = false;
                 break;
             } else if is_initial || i == num_batches - 1 {
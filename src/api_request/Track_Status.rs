@@ -5,6 +5,7 @@ use crate::api_request::token::get_token;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::spawn_local;
 use crate::ui::get_user_id_from_state;
+use crate::mediaplayer::media_session::sync_media_session;
 
 // Fetches the current playback state from Spotify and updates the window state
 #[wasm_bindgen]
@@ -274,6 +275,7 @@ pub async fn pause_playback() {
         web_sys::console::log_1(&"Playback paused via API".into());
         let window = web_sys::window().expect("no global window exists");
         let _ = js_sys::Reflect::set(&window, &"isPlaying".into(), &false.into());
+        sync_media_session();
     }).await;
 }
 
@@ -299,6 +301,7 @@ pub async fn seek_playback(position_ms: i32) {
         web_sys::console::log_2(&"Seek completed via API to:".into(), &position_ms.into());
         let window = web_sys::window().expect("no global window exists");
         let _ = js_sys::Reflect::set(&window, &"currentPlaybackTime".into(), &(position_ms as f64).into());
+        sync_media_session();
     }).await;
 }
 
@@ -370,6 +373,7 @@ pub async fn resume_playback() {
         web_sys::console::log_1(&"Playback resumed via API".into());
         let window = web_sys::window().expect("no global window exists");
         let _ = js_sys::Reflect::set(&window, &"isPlaying".into(), &true.into());
+        sync_media_session();
     }).await;
 }
 
@@ -404,6 +408,9 @@ pub async fn get_current_playback() {
             
             // Set album and images
             let album = js_sys::Object::new();
+            if let Some(album_name) = track.album.name {
+                let _ = js_sys::Reflect::set(&album, &"name".into(), &album_name.into());
+            }
             let images = js_sys::Array::new();
             for image in track.album.images {
                 let img_obj = js_sys::Object::new();
@@ -443,5 +450,106 @@ pub async fn get_current_playback() {
             let _ = js_sys::Reflect::set(&window, &"totalDuration".into(), &0.0.into());
             let _ = js_sys::Reflect::set(&window, &"isPlaying".into(), &false.into());
         }
+
+        // Keep OS media controls in sync with the remote device state
+        sync_media_session();
+    }).await;
+}
+
+// Plays the given tracks on their own, for tracks that aren't part of a library context
+pub async fn play_uris(uris: Vec<String>) {
+    let token = match get_token() {
+        Some(token) => token,
+        None => {
+            web_sys::console::log_1(&"No token available for playing tracks".into());
+            return;
+        }
+    };
+
+    let client = Client::new();
+    let response = client
+        .put("https://api.spotify.com/v1/me/player/play")
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Content-Type", "application/json")
+        .json(&serde_json::json!({ "uris": uris }))
+        .send()
+        .await;
+
+    handle_empty_response(response, || {
+        web_sys::console::log_1(&"Track playback started".into());
+        let window = web_sys::window().expect("no global window exists");
+        let _ = js_sys::Reflect::set(&window, &"isPlaying".into(), &true.into());
+    }).await;
+}
+
+// Plays an album, artist or playlist from the start
+pub async fn play_context(context_uri: String) {
+    let token = match get_token() {
+        Some(token) => token,
+        None => {
+            web_sys::console::log_1(&"No token available for playing context".into());
+            return;
+        }
+    };
+
+    let client = Client::new();
+    let response = client
+        .put("https://api.spotify.com/v1/me/player/play")
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Content-Type", "application/json")
+        .json(&serde_json::json!({ "context_uri": context_uri }))
+        .send()
+        .await;
+
+    handle_empty_response(response, || {
+        web_sys::console::log_1(&"Context playback started".into());
+        let window = web_sys::window().expect("no global window exists");
+        let _ = js_sys::Reflect::set(&window, &"isPlaying".into(), &true.into());
+    }).await;
+}
+
+// Turns shuffle on or off, unlike toggle_shuffle which flips whatever the player has
+pub async fn set_shuffle(shuffle: bool) {
+    let token = match get_token() {
+        Some(token) => token,
+        None => {
+            web_sys::console::log_1(&"No token available for setting shuffle".into());
+            return;
+        }
+    };
+
+    let client = Client::new();
+    let response = client
+        .put("https://api.spotify.com/v1/me/player/shuffle")
+        .query(&[("state", shuffle)])
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await;
+
+    handle_empty_response(response, || {
+        let window = web_sys::window().expect("no global window exists");
+        let _ = js_sys::Reflect::set(&window, &"shuffleState".into(), &shuffle.into());
+    }).await;
+}
+
+pub async fn add_to_queue(uri: String) {
+    let token = match get_token() {
+        Some(token) => token,
+        None => {
+            web_sys::console::log_1(&"No token available for queueing".into());
+            return;
+        }
+    };
+
+    let client = Client::new();
+    let response = client
+        .post("https://api.spotify.com/v1/me/player/queue")
+        .query(&[("uri", &uri)])
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await;
+
+    handle_empty_response(response, || {
+        web_sys::console::log_2(&"Added to queue:".into(), &uri.clone().into());
     }).await;
 }
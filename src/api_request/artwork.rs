@@ -0,0 +1,146 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use futures::StreamExt;
+use image::DynamicImage;
+use once_cell::sync::Lazy;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use crate::utils::log_error;
+
+const MAX_CONCURRENT_DOWNLOADS: usize = 8;
+const MAX_ATTEMPTS: u32 = 3;
+const RETRY_DELAY_MS: u32 = 500; // Doubled after each failed attempt
+const MEMORY_CACHE_BYTES: usize = 256 * 1024 * 1024; // Decoded pixels kept between collage runs
+const CACHE_STORAGE_NAME: &str = "collage-artwork-v1";
+
+// Decoded artwork by URL. The oldest entries are dropped once the pixels outgrow the budget
+#[derive(Default)]
+struct MemoryCache {
+    images: HashMap<String, Arc<DynamicImage>>,
+    order: VecDeque<String>,
+    bytes: usize,
+}
+
+impl MemoryCache {
+    fn insert(&mut self, url: String, image: Arc<DynamicImage>) {
+        if self.images.contains_key(&url) {
+            return;
+        }
+        self.bytes += image.as_bytes().len();
+        self.order.push_back(url.clone());
+        self.images.insert(url, image);
+        while self.bytes > MEMORY_CACHE_BYTES {
+            let Some(oldest) = self.order.pop_front() else {
+                break;
+            };
+            if let Some(image) = self.images.remove(&oldest) {
+                self.bytes -= image.as_bytes().len();
+            }
+        }
+    }
+}
+
+static MEMORY_CACHE: Lazy<Mutex<MemoryCache>> = Lazy::new(|| Mutex::new(MemoryCache::default()));
+
+// Downloads and decodes artwork a few at a time, from the memory cache, then the browser's Cache
+// Storage, then the network. Results keep the order of `urls`, with None for images that failed.
+// Returns None as soon as `is_cancelled` says so; downloads still in flight are dropped
+pub async fn fetch_artwork(
+    urls: &[String],
+    is_cancelled: impl Fn() -> bool,
+    mut on_loaded: impl FnMut(usize),
+) -> Option<Vec<Option<Arc<DynamicImage>>>> {
+    let mut results = vec![None; urls.len()];
+    let mut downloads = futures::stream::iter(urls.iter().cloned().enumerate())
+        .map(|(index, url)| async move { (index, load_artwork(&url).await) })
+        .buffer_unordered(MAX_CONCURRENT_DOWNLOADS);
+
+    let mut loaded = 0;
+    while let Some((index, image)) = downloads.next().await {
+        if is_cancelled() {
+            return None;
+        }
+        results[index] = image;
+        loaded += 1;
+        on_loaded(loaded);
+    }
+    Some(results)
+}
+
+async fn load_artwork(url: &str) -> Option<Arc<DynamicImage>> {
+    if let Some(image) = MEMORY_CACHE.lock().unwrap().images.get(url) {
+        return Some(image.clone());
+    }
+
+    let bytes = match read_cached_bytes(url).await {
+        Some(bytes) => bytes,
+        None => {
+            let bytes = download_with_retry(url).await?;
+            write_cached_bytes(url, &bytes).await;
+            bytes
+        }
+    };
+
+    match image::load_from_memory(&bytes) {
+        Ok(image) => {
+            let image = Arc::new(image);
+            MEMORY_CACHE.lock().unwrap().insert(url.to_string(), image.clone());
+            Some(image)
+        }
+        Err(e) => {
+            log_error(&format!("Failed to decode artwork {}: {:?}", url, e));
+            None
+        }
+    }
+}
+
+// Retries network errors, rate limiting and server errors; other failures won't go away by retrying
+async fn download_with_retry(url: &str) -> Option<Vec<u8>> {
+    let mut delay = RETRY_DELAY_MS;
+    for attempt in 1..=MAX_ATTEMPTS {
+        let retryable = match reqwest::get(url).await {
+            Ok(response) if response.status().is_success() => match response.bytes().await {
+                Ok(bytes) => return Some(bytes.to_vec()),
+                Err(_) => true,
+            },
+            Ok(response) => response.status() == 429 || response.status().is_server_error(),
+            Err(_) => true,
+        };
+        if !retryable || attempt == MAX_ATTEMPTS {
+            break;
+        }
+        gloo_timers::future::TimeoutFuture::new(delay).await;
+        delay *= 2;
+    }
+    log_error(&format!("Failed to download artwork {}", url));
+    None
+}
+
+// The browser's Cache Storage keeps the encoded images across visits. It's missing outside
+// secure contexts, in which case artwork is only cached in memory
+async fn open_cache() -> Option<web_sys::Cache> {
+    let caches = web_sys::window()?.caches().ok()?;
+    let cache = JsFuture::from(caches.open(CACHE_STORAGE_NAME)).await.ok()?;
+    cache.dyn_into::<web_sys::Cache>().ok()
+}
+
+async fn read_cached_bytes(url: &str) -> Option<Vec<u8>> {
+    let cache = open_cache().await?;
+    let response = JsFuture::from(cache.match_with_str(url)).await.ok()?;
+    let response = response.dyn_into::<web_sys::Response>().ok()?;
+    let buffer = JsFuture::from(response.array_buffer().ok()?).await.ok()?;
+    Some(js_sys::Uint8Array::new(&buffer).to_vec())
+}
+
+async fn write_cached_bytes(url: &str, bytes: &[u8]) {
+    let Some(cache) = open_cache().await else {
+        return;
+    };
+    let mut body = bytes.to_vec();
+    let Ok(response) = web_sys::Response::new_with_opt_u8_array(Some(&mut body)) else {
+        return;
+    };
+    if let Err(e) = JsFuture::from(cache.put_with_str(url, &response)).await {
+        log_error(&format!("Failed to cache artwork: {:?}", e));
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::{HashMap, HashSet};
+use reqwest::Client;
+use crate::api_request::models::TrackInfo;
+use crate::api_request::playlist_tracks::fetch_all_playlist_tracks;
+use crate::api_request::library::cached_library;
+use crate::ui::{CollageSource, APP_STATE};
+use crate::utils::log_error;
+
+const RECENTLY_PLAYED_LIMIT: usize = 50; // Spotify only keeps the last 50 plays
+
+// Gathers the tracks of every chosen source, fetching what hasn't been loaded. Tracks in more than
+// one source are kept once, where they first appear
+pub async fn fetch_collage_tracks(token: &str, sources: &[CollageSource]) -> Vec<TrackInfo> {
+    let mut tracks = Vec::new();
+    let mut seen = HashSet::new();
+    for source in sources {
+        set_loading_message(format!("Fetching {}...", source_name(source)));
+        let source_tracks = match source {
+            CollageSource::LikedSongs => fetch_liked_library(token).await,
+            CollageSource::Playlist(id) => fetch_all_playlist_tracks(id, token).await.unwrap_or_default(),
+            CollageSource::TopTracks(range) => fetch_paged(
+                token,
+                format!("https://api.spotify.com/v1/me/top/tracks?limit=50&time_range={}", range.api_value()),
+                usize::MAX,
+                |item| TrackInfo::from_json(item, ""),
+            ).await,
+            CollageSource::TopArtists(range) => fetch_paged(
+                token,
+                format!("https://api.spotify.com/v1/me/top/artists?limit=50&time_range={}", range.api_value()),
+                usize::MAX,
+                artist_as_track,
+            ).await,
+            CollageSource::RecentlyPlayed => fetch_paged(
+                token,
+                "https://api.spotify.com/v1/me/player/recently-played?limit=50".to_string(),
+                RECENTLY_PLAYED_LIMIT,
+                |item| TrackInfo::from_json(&item["track"], item["played_at"].as_str().unwrap_or_default()),
+            ).await,
+        };
+        for track in source_tracks {
+            if seen.insert(track.uri.clone()) {
+                tracks.push(track);
+            }
+        }
+    }
+    tracks
+}
+
+// Playlist names come from the loaded playlists, which the source picker lists
+fn source_name(source: &CollageSource) -> String {
+    match source {
+        CollageSource::Playlist(id) => APP_STATE.lock().unwrap().playlists
+            .iter()
+            .find(|p| &p.id == id)
+            .map(|p| p.name.clone())
+            .unwrap_or_else(|| "playlist".to_string()),
+        _ => source.label(),
+    }
+}
+
+fn set_loading_message(message: String) {
+    APP_STATE.lock().unwrap().loading_message = message;
+}
+
+// The whole library rather than the page Liked Songs has loaded, from the storage cache when it's complete
+async fn fetch_liked_library(token: &str) -> Vec<TrackInfo> {
+    if let Some(stored) = cached_library() {
+        if stored.tracks.len() as i32 >= stored.total {
+            return stored.tracks;
+        }
+    }
+    {
+        let state = APP_STATE.lock().unwrap();
+        if state.total_tracks.is_some_and(|total| state.saved_tracks.len() as i32 >= total) {
+            return state.saved_tracks.to_vec();
+        }
+    }
+    fetch_paged(
+        token,
+        "https://api.spotify.com/v1/me/tracks?limit=50".to_string(),
+        usize::MAX,
+        |item| TrackInfo::from_json(&item["track"], item["added_at"].as_str().unwrap_or_default()),
+    ).await
+}
+
+// Follows an endpoint's next links until max_items have been read or the pages run out
+async fn fetch_paged(token: &str, url: String, max_items: usize, parse: impl Fn(&serde_json::Value) -> Option<TrackInfo>) -> Vec<TrackInfo> {
+    let client = Client::new();
+    let mut tracks = Vec::new();
+    let mut next = Some(url);
+
+    while let Some(url) = next.take() {
+        let response = client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await;
+        let json = match response {
+            Ok(response) if response.status().is_success() => match response.json::<serde_json::Value>().await {
+                Ok(json) => json,
+                Err(e) => {
+                    log_error(&format!("Failed to parse collage source: {:?}", e));
+                    break;
+                }
+            },
+            Ok(response) => {
+                log_error(&format!("Failed to fetch collage source: {:?}", response.status()));
+                break;
+            }
+            Err(e) => {
+                log_error(&format!("Request error: {:?}", e));
+                break;
+            }
+        };
+
+        let items = json["items"].as_array().cloned().unwrap_or_default();
+        tracks.extend(items.iter().filter_map(&parse));
+        if tracks.len() >= max_items {
+            tracks.truncate(max_items);
+            break;
+        }
+        next = json["next"].as_str().map(|url| url.to_string());
+    }
+    tracks
+}
+
+// The user's top artists, whose images can be recreated as photomosaics
+pub async fn fetch_top_artists(token: &str) -> Vec<TrackInfo> {
+    fetch_paged(
+        token,
+        "https://api.spotify.com/v1/me/top/artists?limit=50&time_range=medium_term".to_string(),
+        50,
+        artist_as_track,
+    ).await
+}
+
+// Artists pose as tracks so their images go through the same pipeline as album art
+fn artist_as_track(artist: &serde_json::Value) -> Option<TrackInfo> {
+    Some(TrackInfo {
+        name: artist["name"].as_str()?.to_string(),
+        image_url: artist["images"][0]["url"].as_str()?.to_string(),
+        uri: artist["uri"].as_str()?.to_string(),
+        popularity: artist["popularity"].as_i64().unwrap_or_default() as i32,
+        ..Default::default()
+    })
+}
+
+// One piece of artwork in the collage, shared by every track on the same album
+pub struct CollageArtwork {
+    pub key: String, // Album or artist URI, or the image URL when neither is known
+    pub name: String, // Album or artist name
+    pub images: Vec<(u32, String)>, // (width, url) of each available size
+    pub track_count: usize,
+}
+
+impl CollageArtwork {
+    // The smallest image at least `size` wide, or the largest there is
+    pub fn image_for(&self, size: u32) -> Option<&str> {
+        let mut images: Vec<&(u32, String)> = self.images.iter().collect();
+        images.sort_by_key(|(width, _)| *width);
+        images
+            .iter()
+            .find(|(width, _)| *width >= size)
+            .or(images.last())
+            .map(|(_, url)| url.as_str())
+    }
+}
+
+// Groups tracks by their artwork, keeping the order each artwork first appears in. Tracks cached
+// before album URIs were stored are grouped by image URL, which is shared by an album's tracks too
+pub fn group_by_artwork(tracks: &[TrackInfo]) -> Vec<CollageArtwork> {
+    let mut artworks: Vec<CollageArtwork> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for track in tracks.iter().filter(|track| !track.image_url.is_empty()) {
+        let key = if !track.album_uri.is_empty() {
+            track.album_uri.clone()
+        } else if track.uri.starts_with("spotify:artist:") {
+            track.uri.clone()
+        } else {
+            track.image_url.clone()
+        };
+        match positions.get(&key) {
+            Some(&index) => artworks[index].track_count += 1,
+            None => {
+                positions.insert(key.clone(), artworks.len());
+                artworks.push(CollageArtwork {
+                    key,
+                    name: if track.album.is_empty() { track.name.clone() } else { track.album.clone() },
+                    // The size of the image tracks carry isn't known, so it only wins when nothing else is found
+                    images: vec![(0, track.image_url.clone())],
+                    track_count: 1,
+                });
+            }
+        }
+    }
+    artworks
+}
+
+// Looks up every size of each album's and artist's images, so the collage can download the one
+// that matches its tile size
+pub async fn resolve_artwork_sizes(token: &str, artworks: &mut [CollageArtwork]) {
+    set_loading_message("Looking up artwork sizes...".to_string());
+    let client = Client::new();
+    for (kind, batch_size) in [("album", 20), ("artist", 50)] {
+        let prefix = format!("spotify:{}:", kind);
+        let ids: Vec<String> = artworks
+            .iter()
+            .filter_map(|artwork| artwork.key.strip_prefix(&prefix).map(|id| id.to_string()))
+            .collect();
+
+        for batch in ids.chunks(batch_size) {
+            let response = client
+                .get(format!("https://api.spotify.com/v1/{}s", kind))
+                .query(&[("ids", batch.join(","))])
+                .header("Authorization", format!("Bearer {}", token))
+                .send()
+                .await;
+            let json = match response {
+                Ok(response) if response.status().is_success() => match response.json::<serde_json::Value>().await {
+                    Ok(json) => json,
+                    Err(e) => {
+                        log_error(&format!("Failed to parse artwork sizes: {:?}", e));
+                        continue;
+                    }
+                },
+                Ok(response) => {
+                    log_error(&format!("Failed to fetch artwork sizes: {:?}", response.status()));
+                    continue;
+                }
+                Err(e) => {
+                    log_error(&format!("Request error: {:?}", e));
+                    continue;
+                }
+            };
+
+            // Unknown IDs come back as nulls, which are skipped
+            for item in json[format!("{}s", kind)].as_array().cloned().unwrap_or_default() {
+                let Some(uri) = item["uri"].as_str() else {
+                    continue;
+                };
+                let images: Vec<(u32, String)> = item["images"].as_array().cloned().unwrap_or_default()
+                    .iter()
+                    .filter_map(|image| Some((image["width"].as_u64().unwrap_or_default() as u32, image["url"].as_str()?.to_string())))
+                    .collect();
+                if images.is_empty() {
+                    continue;
+                }
+                if let Some(artwork) = artworks.iter_mut().find(|artwork| artwork.key == uri) {
+                    artwork.images = images;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use reqwest::Client;
+use crate::api_request::models::{StoredTracks, TrackInfo};
+use crate::api_request::spotify_apis::handle_empty_response;
+use crate::api_request::token::get_token;
+use crate::storage::load_tracks;
+use crate::ui::{AppState, APP_STATE};
+use crate::utils::log_error;
+
+const LIBRARY_BATCH_SIZE: usize = 50; // Most IDs the /me/tracks endpoints accept per request
+
+// Where an unliked track sat in the loaded and cached library, so a failed request can put it back
+struct RemovedTrack {
+    track: TrackInfo,
+    loaded_index: Option<usize>,
+    cached_index: Option<usize>,
+}
+
+pub async fn set_track_liked(token: String, track: TrackInfo, liked: bool) {
+    set_tracks_liked(token, vec![track], liked).await;
+}
+
+// Saves tracks to or removes them from Liked Songs, 50 per request. The library and its storage
+// cache are updated before each request is sent and rolled back if it fails
+pub async fn set_tracks_liked(token: String, tracks: Vec<TrackInfo>, liked: bool) {
+    let client = Client::new();
+    for batch in tracks.chunks(LIBRARY_BATCH_SIZE) {
+        let removed = {
+            let mut state = APP_STATE.lock().unwrap();
+            let mut removed = Vec::new();
+            edit_cached_library(&mut state, |state, cache| {
+                if liked {
+                    // Inserted last to first, so the batch keeps its order at the top of the library
+                    let added_at = String::from(js_sys::Date::new_0().to_iso_string());
+                    for track in batch.iter().rev() {
+                        let mut track = track.clone();
+                        track.added_at = added_at.clone();
+                        insert_liked(state, cache, track, 0, 0);
+                    }
+                } else {
+                    removed = batch.iter().map(|track| remove_liked(state, cache, track)).collect();
+                }
+            });
+            removed
+        };
+
+        let ids = batch.iter().map(|track| track.id()).collect::<Vec<_>>().join(",");
+        let request = if liked {
+            client.put("https://api.spotify.com/v1/me/tracks")
+        } else {
+            client.delete("https://api.spotify.com/v1/me/tracks")
+        };
+        let response = request
+            .query(&[("ids", ids)])
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await;
+
+        let mut succeeded = false;
+        handle_empty_response(response, || succeeded = true).await;
+        if succeeded {
+            continue;
+        }
+
+        let mut state = APP_STATE.lock().unwrap();
+        edit_cached_library(&mut state, |state, cache| {
+            if liked {
+                for track in batch {
+                    remove_liked(state, cache, track);
+                }
+                return;
+            }
+            // Undo in reverse, since each removal's indices assume the earlier ones already happened
+            for removed in removed.into_iter().rev() {
+                match removed.loaded_index {
+                    Some(loaded_index) => {
+                        let cached_index = removed.cached_index.unwrap_or(loaded_index);
+                        insert_liked(state, cache, removed.track, loaded_index, cached_index);
+                    }
+                    // Only put it back into the loaded page if it was loaded before
+                    None => {
+                        state.liked_tracks.insert(removed.track.id().to_string(), true);
+                        if let (Some(stored), Some(cached_index)) = (cache.as_mut(), removed.cached_index) {
+                            stored.tracks.insert(cached_index.min(stored.tracks.len()), removed.track);
+                            stored.total += 1;
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
+
+fn insert_liked(state: &mut AppState, cache: &mut Option<StoredTracks>, track: TrackInfo, loaded_index: usize, cached_index: usize) {
+    state.liked_tracks.insert(track.id().to_string(), true);
+
+    // The loaded page has to stay a prefix of the cache, so only touch it once the library is loaded
+    if state.total_tracks.is_some() && !state.saved_tracks.iter().any(|saved| saved.uri == track.uri) {
+        let tracks = state.saved_tracks.make_mut();
+        tracks.insert(loaded_index.min(tracks.len()), track.clone());
+        state.loaded_tracks_count += 1;
+        state.total_tracks = state.total_tracks.map(|total| total + 1);
+    }
+    if let Some(stored) = cache.as_mut() {
+        if !stored.tracks.iter().any(|cached| cached.uri == track.uri) {
+            stored.tracks.insert(cached_index.min(stored.tracks.len()), track);
+            stored.total += 1;
+        }
+    }
+}
+
+fn remove_liked(state: &mut AppState, cache: &mut Option<StoredTracks>, track: &TrackInfo) -> RemovedTrack {
+    state.liked_tracks.insert(track.id().to_string(), false);
+
+    let loaded_index = state.saved_tracks.iter().position(|saved| saved.uri == track.uri);
+    let mut removed = track.clone();
+    if let Some(index) = loaded_index {
+        removed = state.saved_tracks.make_mut().remove(index);
+        state.loaded_tracks_count -= 1;
+        state.total_tracks = state.total_tracks.map(|total| total - 1);
+    }
+    let mut cached_index = None;
+    if let Some(stored) = cache.as_mut() {
+        cached_index = stored.tracks.iter().position(|cached| cached.uri == track.uri);
+        if let Some(index) = cached_index {
+            removed = stored.tracks.remove(index);
+            stored.total -= 1;
+        }
+    }
+
+    RemovedTrack { track: removed, loaded_index, cached_index }
+}
+
+// The cache holds the whole library while the view only loads a page of it, so edits go to both.
+// Edits stay in memory and the session saver writes them out, since the cache can be large
+fn edit_cached_library(state: &mut AppState, edit: impl FnOnce(&mut AppState, &mut Option<StoredTracks>)) {
+    let mut cache = state.unsaved_library.take().or_else(load_tracks);
+    edit(state, &mut cache);
+    state.unsaved_library = cache;
+}
+
+// The storage cache of the whole library, including likes that haven't been written out yet
+pub fn cached_library() -> Option<StoredTracks> {
+    let unsaved = APP_STATE.lock().unwrap().unsaved_library.clone();
+    unsaved.or_else(load_tracks)
+}
+
+// Sends the next batch of track IDs queued by the heart buttons; called once per frame
+pub fn flush_liked_checks() {
+    let ids: Vec<String> = {
+        let mut state = APP_STATE.lock().unwrap();
+        if state.queued_liked_checks.is_empty() {
+            return;
+        }
+        let count = state.queued_liked_checks.len().min(LIBRARY_BATCH_SIZE);
+        state.queued_liked_checks.drain(..count).collect()
+    };
+    let token = match get_token() {
+        Some(token) => token,
+        None => return,
+    };
+    wasm_bindgen_futures::spawn_local(async move {
+        check_saved_tracks(token, ids).await;
+    });
+}
+
+async fn check_saved_tracks(token: String, ids: Vec<String>) {
+    let client = Client::new();
+    let response = client
+        .get("https://api.spotify.com/v1/me/tracks/contains")
+        .query(&[("ids", ids.join(","))])
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await;
+
+    // Failed IDs stay marked as requested, so they aren't retried every frame
+    match response {
+        Ok(response) if response.status().is_success() => match response.json::<Vec<bool>>().await {
+            Ok(saved) => {
+                let mut state = APP_STATE.lock().unwrap();
+                for (id, liked) in ids.into_iter().zip(saved) {
+                    // A toggle made while the lookup was in flight is newer
+                    state.liked_tracks.entry(id).or_insert(liked);
+                }
+            }
+            Err(e) => log_error(&format!("Failed to parse liked status: {:?}", e)),
+        },
+        Ok(response) => log_error(&format!("Failed to check liked status: {:?}", response.status())),
+        Err(e) => log_error(&format!("Request error: {:?}", e)),
+    }
+}
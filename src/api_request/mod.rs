@@ -7,3 +7,8 @@ pub mod user_profile;
 pub mod saved_tracks;
 pub mod track_status;
 pub mod playlist_tracks;
+pub mod search;
+pub mod library;
+pub mod playlist_edit;
+pub mod collage_sources;
+pub mod artwork;
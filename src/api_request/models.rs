@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize)]
 pub struct SavedTrack {
+    pub added_at: String,
     pub track: Track,
 }
 
@@ -11,11 +12,19 @@ pub struct Track {
     pub artists: Vec<Artist>,
     pub album: Album,
     pub duration_ms: i32,
+    #[serde(default)]
+    pub popularity: i32, // Missing for local files
+    #[serde(default)]
+    pub explicit: bool,
     pub uri: String,  // Spotify URI for the track (e.g. "spotify:track:...")
 }
 
 #[derive(Deserialize)]
 pub struct Album {
+    pub name: Option<String>,
+    #[serde(default)]
+    pub uri: String,
+    pub release_date: Option<String>,
     pub images: Vec<Image>,
 }
 
@@ -28,6 +37,8 @@ pub struct Image {
 #[derive(Deserialize)]
 pub struct Artist {
     pub name: String,
+    #[serde(default)]
+    pub uri: String,
 }
 
 #[derive(Deserialize)]
@@ -42,13 +53,122 @@ pub struct UserProfile {
     pub id: Option<String>, // Add this new field
 }
 
-#[derive(Serialize, Deserialize)]
+// A track as shown in the track views and cached in localStorage
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct TrackInfo {
+    pub name: String,
+    pub artists: String, // Comma separated artist names
+    pub image_url: String,
+    pub uri: String, // Spotify URI; playlist ID when playlists are shown through the track views
+    pub album: String,
+    pub duration_ms: i32,
+    pub added_at: String, // ISO 8601 timestamp
+    pub popularity: i32,
+    #[serde(default)]
+    pub release_date: String, // "YYYY", "YYYY-MM" or "YYYY-MM-DD"
+    #[serde(default)]
+    pub explicit: bool,
+    #[serde(default)]
+    pub album_uri: String,
+    #[serde(default)]
+    pub artist_uri: String, // First artist only
+}
+
+impl TrackInfo {
+    // Builds a track from a Web API track object, for endpoints read as untyped JSON
+    pub fn from_json(track: &serde_json::Value, added_at: &str) -> Option<Self> {
+        let track = track.as_object()?;
+        let artists = track.get("artists")?.as_array()?
+            .iter()
+            .filter_map(|artist| artist["name"].as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let album = track.get("album")?;
+
+        Some(TrackInfo {
+            name: track.get("name")?.as_str()?.to_string(),
+            artists,
+            image_url: album["images"][0]["url"].as_str().unwrap_or_default().to_string(),
+            uri: track.get("uri")?.as_str()?.to_string(),
+            album: album["name"].as_str().unwrap_or_default().to_string(),
+            duration_ms: track.get("duration_ms").and_then(|ms| ms.as_i64()).unwrap_or_default() as i32,
+            added_at: added_at.to_string(),
+            popularity: track.get("popularity").and_then(|p| p.as_i64()).unwrap_or_default() as i32,
+            release_date: album["release_date"].as_str().unwrap_or_default().to_string(),
+            explicit: track.get("explicit").and_then(|explicit| explicit.as_bool()).unwrap_or_default(),
+            album_uri: album["uri"].as_str().unwrap_or_default().to_string(),
+            artist_uri: track["artists"][0]["uri"].as_str().unwrap_or_default().to_string(),
+        })
+    }
+
+    // The ID part of a "spotify:track:<id>" URI
+    pub fn id(&self) -> &str {
+        self.uri.rsplit(':').next().unwrap_or_default()
+    }
+}
+
+// A playlist as listed in the playlist browser
+#[derive(Clone, Default)]
+pub struct PlaylistInfo {
+    pub name: String,
+    pub owner: String, // Owner's display name
+    pub owner_id: String,
+    pub image_url: String,
+    pub id: String,
+    pub total_tracks: i32,
+    pub description: String,
+    pub public: bool,
+    pub collaborative: bool,
+}
+
+impl PlaylistInfo {
+    // Builds a playlist from a Web API simplified playlist object
+    pub fn from_json(playlist: &serde_json::Value) -> Option<Self> {
+        Some(PlaylistInfo {
+            name: playlist["name"].as_str().unwrap_or_default().to_string(),
+            owner: playlist["owner"]["display_name"].as_str().unwrap_or_default().to_string(),
+            owner_id: playlist["owner"]["id"].as_str().unwrap_or_default().to_string(),
+            image_url: playlist["images"][0]["url"].as_str().unwrap_or_default().to_string(),
+            id: playlist["id"].as_str()?.to_string(),
+            total_tracks: playlist["tracks"]["total"].as_i64().unwrap_or_default() as i32,
+            description: playlist["description"].as_str().unwrap_or_default().to_string(),
+            public: playlist["public"].as_bool().unwrap_or_default(),
+            collaborative: playlist["collaborative"].as_bool().unwrap_or_default(),
+        })
+    }
+
+    // Only the owner can change a playlist's details, but collaborators can edit its tracks
+    pub fn is_owned_by(&self, user_id: &str) -> bool {
+        !user_id.is_empty() && self.owner_id == user_id
+    }
+
+    pub fn is_editable_by(&self, user_id: &str) -> bool {
+        self.is_owned_by(user_id) || self.collaborative
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct StoredTracks {
-    pub tracks: Vec<(String, String, String, String)>, // (track name, artist name, image url, uri)
+    #[serde(default)]
+    pub version: u32, // Missing from the first format, which stored (name, artists, image URL, URI) tuples
+    pub tracks: Vec<TrackInfo>,
     pub total: i32,
     pub timestamp: u64,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct StoredPlaylist {
+    pub name: String,
+    #[serde(default)]
+    pub snapshot_id: String,
+    pub tracks: Vec<TrackInfo>,
+    #[serde(default)]
+    pub positions: Vec<usize>, // Playlist position of each track; empty when they're all in order
+    #[serde(default)]
+    pub complete: bool,
+    pub timestamp: u64,
+}
+
 #[derive(Deserialize)]
 pub struct CurrentPlaybackResponse {
     pub item: Option<Track>,
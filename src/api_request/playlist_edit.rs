@@ -0,0 +1,365 @@
+use reqwest::{Client, RequestBuilder};
+use crate::api_request::playlist_tracks::{fetch_all_playlist_tracks, fetch_complete_playlist_tracks, fetch_playlist_tracks};
+use crate::api_request::models::PlaylistInfo;
+use crate::api_request::spotify_apis::handle_empty_response;
+use crate::storage::save_playlist_tracks;
+use crate::ui::{PlaylistDraft, TrackList, APP_STATE};
+use crate::utils::{clear_token_and_redirect, log_error};
+
+const PLAYLIST_BATCH_SIZE: usize = 100; // Most items the playlist items endpoints accept per request
+
+fn playlist_tracks_url(playlist_id: &str) -> String {
+    format!("https://api.spotify.com/v1/playlists/{}/tracks", playlist_id)
+}
+
+// Snapshot of the playlist the open window shows, so edits are applied to the version the user saw
+fn window_snapshot(playlist_id: &str) -> Option<String> {
+    let state = APP_STATE.lock().unwrap();
+    state.playlist_windows
+        .iter()
+        .find(|w| w.id == playlist_id && !w.snapshot_id.is_empty())
+        .map(|w| w.snapshot_id.clone())
+}
+
+// Reloads the playlist's open window, as far as it was loaded before
+async fn refresh_window(playlist_id: String, token: String) {
+    let complete = {
+        let state = APP_STATE.lock().unwrap();
+        match state.playlist_windows.iter().find(|w| w.id == playlist_id) {
+            Some(window) => window.complete,
+            None => return,
+        }
+    };
+    if complete {
+        fetch_complete_playlist_tracks(playlist_id, token).await;
+    } else {
+        fetch_playlist_tracks(playlist_id, token).await;
+    }
+}
+
+// Sends a playlist edit and keeps the snapshot_id Spotify returns for the next edit, so the window's
+// optimistic changes stand. Callers refresh the window when an edit fails
+async fn send_edit(request: RequestBuilder, token: String, playlist_id: String) -> bool {
+    let response = request
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Content-Type", "application/json")
+        .send()
+        .await;
+
+    match response {
+        Ok(response) if response.status().is_success() => {
+            if let Ok(json) = response.json::<serde_json::Value>().await {
+                if let Some(snapshot_id) = json["snapshot_id"].as_str() {
+                    let mut state = APP_STATE.lock().unwrap();
+                    if let Some(window) = state.playlist_windows.iter_mut().find(|w| w.id == playlist_id) {
+                        window.snapshot_id = snapshot_id.to_string();
+                        // The stored copy follows the window, as a refetch would have saved it
+                        if let Err(e) = save_playlist_tracks(&window.id, &window.name, snapshot_id, &window.tracks, window.complete) {
+                            log_error(&format!("Failed to save playlist to storage: {}", e));
+                        }
+                    }
+                }
+            }
+            true
+        }
+        Ok(response) if response.status() == 401 => {
+            clear_token_and_redirect();
+            false
+        }
+        // Includes edits refused because the playlist changed since the window's snapshot
+        Ok(response) => {
+            log_error(&format!("Failed to edit playlist: {:?}", response.status()));
+            false
+        }
+        Err(e) => {
+            log_error(&format!("Request error: {:?}", e));
+            false
+        }
+    }
+}
+
+// Adds tracks to a playlist at a position, or at the end without one. An open window is reloaded
+// once they're all in, since it has no tracks to show until then
+pub async fn add_tracks_to_playlist(token: String, playlist_id: String, uris: Vec<String>, position: Option<usize>) {
+    for (batch_index, batch) in uris.chunks(PLAYLIST_BATCH_SIZE).enumerate() {
+        let mut body = serde_json::json!({ "uris": batch });
+        if let Some(position) = position {
+            body["position"] = (position + batch_index * PLAYLIST_BATCH_SIZE).into();
+        }
+        let request = Client::new()
+            .post(playlist_tracks_url(&playlist_id))
+            .json(&body);
+
+        if send_edit(request, token.clone(), playlist_id.clone()).await {
+            let mut state = APP_STATE.lock().unwrap();
+            if let Some(playlist) = state.playlists.iter_mut().find(|p| p.id == playlist_id) {
+                playlist.total_tracks += batch.len() as i32;
+            }
+        }
+    }
+    refresh_window(playlist_id, token).await;
+}
+
+// Removes every occurrence of the tracks from a playlist; the window drops them right away, and is
+// only reloaded if a batch fails
+pub async fn remove_tracks_from_playlist(token: String, playlist_id: String, uris: Vec<String>) {
+    let window_tracks = {
+        let mut state = APP_STATE.lock().unwrap();
+        match state.playlist_windows.iter_mut().find(|w| w.id == playlist_id) {
+            Some(window) => {
+                let tracks = window.tracks.clone();
+                window.tracks.remove_uris(&uris);
+                tracks
+            }
+            None => TrackList::default(),
+        }
+    };
+
+    let mut failed = false;
+    for batch in uris.chunks(PLAYLIST_BATCH_SIZE) {
+        let removed = window_tracks.iter().filter(|t| batch.contains(&t.uri)).count();
+        let tracks: Vec<_> = batch.iter().map(|uri| serde_json::json!({ "uri": uri })).collect();
+        let mut body = serde_json::json!({ "tracks": tracks });
+        if let Some(snapshot_id) = window_snapshot(&playlist_id) {
+            body["snapshot_id"] = snapshot_id.into();
+        }
+        let request = Client::new()
+            .delete(playlist_tracks_url(&playlist_id))
+            .json(&body);
+
+        if send_edit(request, token.clone(), playlist_id.clone()).await {
+            let mut state = APP_STATE.lock().unwrap();
+            if let Some(playlist) = state.playlists.iter_mut().find(|p| p.id == playlist_id) {
+                playlist.total_tracks -= removed as i32;
+            }
+        } else {
+            failed = true;
+        }
+    }
+    if failed {
+        refresh_window(playlist_id, token).await;
+    }
+}
+
+// Moves the item at playlist position range_start so it sits before the item currently at insert_before
+pub async fn reorder_playlist_track(token: String, playlist_id: String, range_start: usize, insert_before: usize) {
+    if insert_before == range_start || insert_before == range_start + 1 {
+        return;
+    }
+
+    {
+        let mut state = APP_STATE.lock().unwrap();
+        if let Some(window) = state.playlist_windows.iter_mut().find(|w| w.id == playlist_id) {
+            window.tracks.move_item(range_start, insert_before);
+        }
+    }
+
+    let mut body = serde_json::json!({
+        "range_start": range_start,
+        "insert_before": insert_before,
+        "range_length": 1,
+    });
+    if let Some(snapshot_id) = window_snapshot(&playlist_id) {
+        body["snapshot_id"] = snapshot_id.into();
+    }
+    let request = Client::new()
+        .put(playlist_tracks_url(&playlist_id))
+        .json(&body);
+
+    if !send_edit(request, token.clone(), playlist_id.clone()).await {
+        refresh_window(playlist_id, token).await;
+    }
+}
+
+// Creates a playlist owned by the user and lists it first in the playlist browser. Returns its ID
+pub async fn create_playlist(token: String, draft: PlaylistDraft) -> Option<String> {
+    let user_id = match APP_STATE.lock().unwrap().user_id.clone() {
+        Some(user_id) => user_id,
+        None => {
+            log_error("Can't create a playlist before the user profile has loaded");
+            return None;
+        }
+    };
+
+    let response = Client::new()
+        .post(format!("https://api.spotify.com/v1/users/{}/playlists", user_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Content-Type", "application/json")
+        .json(&details_body(&draft))
+        .send()
+        .await;
+
+    match response {
+        Ok(response) if response.status().is_success() => {
+            let playlist = match response.json::<serde_json::Value>().await {
+                Ok(json) => PlaylistInfo::from_json(&json)?,
+                Err(e) => {
+                    log_error(&format!("Failed to parse created playlist: {:?}", e));
+                    return None;
+                }
+            };
+            let id = playlist.id.clone();
+            APP_STATE.lock().unwrap().playlists.insert(0, playlist);
+            Some(id)
+        }
+        Ok(response) if response.status() == 401 => {
+            clear_token_and_redirect();
+            None
+        }
+        Ok(response) => {
+            log_error(&format!("Failed to create playlist: {:?}", response.status()));
+            None
+        }
+        Err(e) => {
+            log_error(&format!("Request error: {:?}", e));
+            None
+        }
+    }
+}
+
+// Saves a playlist's name, description and visibility. The browser and any open window show the
+// new details right away and go back to the old ones if the request fails
+pub async fn update_playlist_details(token: String, draft: PlaylistDraft) {
+    let playlist_id = match draft.id.clone() {
+        Some(id) => id,
+        None => return,
+    };
+
+    let previous = {
+        let mut state = APP_STATE.lock().unwrap();
+        let previous = state.playlists.iter().find(|p| p.id == playlist_id).cloned();
+        if let Some(playlist) = state.playlists.iter_mut().find(|p| p.id == playlist_id) {
+            playlist.name = draft.name.clone();
+            playlist.description = draft.description.clone();
+            playlist.public = draft.public;
+            playlist.collaborative = draft.collaborative;
+        }
+        if let Some(window) = state.playlist_windows.iter_mut().find(|w| w.id == playlist_id) {
+            window.name = draft.name.clone();
+        }
+        previous
+    };
+
+    let response = Client::new()
+        .put(format!("https://api.spotify.com/v1/playlists/{}", playlist_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Content-Type", "application/json")
+        .json(&details_body(&draft))
+        .send()
+        .await;
+
+    let mut succeeded = false;
+    handle_empty_response(response, || succeeded = true).await;
+    if succeeded {
+        return;
+    }
+
+    if let Some(previous) = previous {
+        let mut state = APP_STATE.lock().unwrap();
+        if let Some(window) = state.playlist_windows.iter_mut().find(|w| w.id == playlist_id) {
+            window.name = previous.name.clone();
+        }
+        if let Some(playlist) = state.playlists.iter_mut().find(|p| p.id == playlist_id) {
+            *playlist = previous;
+        }
+    }
+}
+
+// Spotify has no playlist deletion; unfollowing removes it from the user's library, which is
+// what deleting does in Spotify's own apps
+pub async fn unfollow_playlist(token: String, playlist_id: String) {
+    let response = Client::new()
+        .delete(format!("https://api.spotify.com/v1/playlists/{}/followers", playlist_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await;
+
+    handle_empty_response(response, || {
+        let mut state = APP_STATE.lock().unwrap();
+        state.playlists.retain(|p| p.id != playlist_id);
+        state.playlist_windows.retain(|w| w.id != playlist_id);
+    }).await;
+}
+
+// Collaborative playlists can't be public, so the flags are sent the way Spotify accepts them
+fn details_body(draft: &PlaylistDraft) -> serde_json::Value {
+    serde_json::json!({
+        "name": draft.name.trim(),
+        "description": draft.description,
+        "public": draft.public && !draft.collaborative,
+        "collaborative": draft.collaborative,
+    })
+}
+
+// Copies a playlist's tracks into a new private playlist of the user's
+pub async fn duplicate_playlist(token: String, playlist_id: String, name: String) {
+    let tracks = match fetch_all_playlist_tracks(&playlist_id, &token).await {
+        Some(tracks) => tracks,
+        None => return,
+    };
+    let draft = PlaylistDraft {
+        name: format!("{} (copy)", name),
+        ..Default::default()
+    };
+    let new_id = match create_playlist(token.clone(), draft).await {
+        Some(id) => id,
+        None => return,
+    };
+
+    // Local files can't be added through the API
+    let uris: Vec<String> = tracks
+        .into_iter()
+        .map(|track| track.uri)
+        .filter(|uri| !uri.starts_with("spotify:local:"))
+        .collect();
+    add_tracks_to_playlist(token, new_id, uris, None).await;
+}
+
+// Replaces a playlist's cover with a base64 encoded JPEG, then picks up the new artwork. Spotify
+// processes uploads in the background, so the images are read back after a short wait
+pub async fn upload_playlist_cover(token: String, playlist_id: String, jpeg_base64: String) {
+    APP_STATE.lock().unwrap().cover_uploading = true;
+
+    let response = Client::new()
+        .put(format!("https://api.spotify.com/v1/playlists/{}/images", playlist_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Content-Type", "image/jpeg")
+        .body(jpeg_base64)
+        .send()
+        .await;
+
+    let mut succeeded = false;
+    handle_empty_response(response, || succeeded = true).await;
+    if succeeded {
+        gloo_timers::future::TimeoutFuture::new(2_000).await;
+        refresh_playlist_image(&token, &playlist_id).await;
+    }
+
+    APP_STATE.lock().unwrap().cover_uploading = false;
+}
+
+async fn refresh_playlist_image(token: &str, playlist_id: &str) {
+    let response = Client::new()
+        .get(format!("https://api.spotify.com/v1/playlists/{}/images", playlist_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await;
+
+    match response {
+        Ok(response) if response.status().is_success() => match response.json::<serde_json::Value>().await {
+            Ok(images) => {
+                // The first image is the largest, as in the playlist listing
+                let Some(image_url) = images[0]["url"].as_str() else {
+                    return;
+                };
+                let mut state = APP_STATE.lock().unwrap();
+                if let Some(playlist) = state.playlists.iter_mut().find(|p| p.id == playlist_id) {
+                    playlist.image_url = image_url.to_string();
+                }
+            }
+            Err(e) => log_error(&format!("Failed to parse playlist images: {:?}", e)),
+        },
+        Ok(response) => log_error(&format!("Failed to fetch playlist images: {:?}", response.status())),
+        Err(e) => log_error(&format!("Request error: {:?}", e)),
+    }
+}
@@ -1,9 +1,22 @@
 use reqwest::Client;
 use crate::ui::APP_STATE;
-use crate::utils::log_error;
-use crate::ui::ViewMode;
+use crate::utils::{download_file, log_error};
+use crate::storage::save_playlist_tracks;
+use crate::ui::{PlaylistWindow, TrackList};
+use super::models::TrackInfo;
 
 pub async fn fetch_playlist_tracks(playlist_id: String, token: String) {
+    load_playlist(playlist_id, token, false).await;
+}
+
+// Like fetch_playlist_tracks, but pages through the rest of the playlist too, so a window that
+// showed every track still does after it's refreshed
+pub async fn fetch_complete_playlist_tracks(playlist_id: String, token: String) {
+    load_playlist(playlist_id, token, true).await;
+}
+
+// Loads a playlist into its window, with the first 100 tracks the playlist object embeds or all of them
+async fn load_playlist(playlist_id: String, token: String, all: bool) {
     let client = Client::new();
     let url = format!(
         "https://api.spotify.com/v1/playlists/{}",
@@ -23,38 +36,46 @@ pub async fn fetch_playlist_tracks(playlist_id: String, token: String) {
         if resp.status().is_success() {
             if let Ok(json) = resp.json::<serde_json::Value>().await {
                 let playlist_name = json["name"].as_str().unwrap_or("Selected Playlist").to_string();
-                let items = json["tracks"]["items"].as_array().cloned().unwrap_or_default();
-                let tracks_data = items.into_iter()
-                    .filter_map(|item| {
-                        let track = item["track"].as_object()?;
-                        let name = track["name"].as_str()?.to_string();
-                        
-                        let artists = track["artists"].as_array()?
-                            .iter()
-                            .filter_map(|artist| artist["name"].as_str())
-                            .collect::<Vec<_>>()
-                            .join(", ");
-
-                        let album = track["album"].as_object()?;
-                        let image_url = album["images"].as_array()?
-                            .first()?["url"].as_str()?
-                            .to_string();
-                            
-                        let uri = track["uri"].as_str()?.to_string();
-
-                        Some((name, artists, image_url, uri))
+                let snapshot_id = json["snapshot_id"].as_str().unwrap_or_default().to_string();
+                let mut items = json["tracks"]["items"].as_array().cloned().unwrap_or_default();
+                let mut next = json["tracks"]["next"].as_str().filter(|_| all).map(|url| url.to_string());
+                while let Some(url) = next {
+                    let Some((page, page_next)) = fetch_items_page(&client, &url, &token).await else {
+                        break;
+                    };
+                    items.extend(page);
+                    next = page_next;
+                }
+                let complete = json["tracks"]["total"].as_u64().is_some_and(|total| items.len() as u64 >= total);
+                // Unavailable items aren't shown but still take up a position in the playlist
+                let (positions, tracks): (Vec<usize>, Vec<TrackInfo>) = items.iter()
+                    .enumerate()
+                    .filter_map(|(position, item)| {
+                        TrackInfo::from_json(&item["track"], item["added_at"].as_str().unwrap_or_default())
+                            .map(|track| (position, track))
                     })
-                    .collect::<Vec<_>>();
+                    .unzip();
+                let tracks_data = TrackList::with_positions(tracks, positions);
+
+                if let Err(e) = save_playlist_tracks(&playlist_id, &playlist_name, &snapshot_id, &tracks_data, complete) {
+                    log_error(&format!("Failed to save playlist to storage: {}", e));
+                }
 
                 let mut state = APP_STATE.lock().unwrap();
-                state.playlist_windows.push((
-                    playlist_id,  // Store the actual playlist ID
-                    playlist_name, 
-                    tracks_data, 
-                    ViewMode::List, 
-                    true, 
-                    (500.0, 100.0)
-                ));
+                // Refresh an already open window in place instead of opening a duplicate
+                if let Some(window_state) = state.playlist_windows.iter_mut().find(|w| w.id == playlist_id) {
+                    window_state.name = playlist_name;
+                    window_state.tracks = tracks_data;
+                    window_state.complete = complete;
+                    window_state.snapshot_id = snapshot_id;
+                    window_state.open = true;
+                } else {
+                    let mut window_state = PlaylistWindow::new(playlist_id, playlist_name);
+                    window_state.tracks = tracks_data;
+                    window_state.complete = complete;
+                    window_state.snapshot_id = snapshot_id;
+                    state.playlist_windows.push(window_state);
+                }
                 state.is_loading = false;
             }
         } else {
@@ -64,3 +85,95 @@ pub async fn fetch_playlist_tracks(playlist_id: String, token: String) {
         log_error("Request error while fetching playlist tracks");
     }
 }
+
+// Every track of a playlist, paging past the first 100 the playlist object embeds
+pub async fn fetch_all_playlist_tracks(playlist_id: &str, token: &str) -> Option<Vec<TrackInfo>> {
+    let client = Client::new();
+    let mut tracks = Vec::new();
+    let mut next = Some(format!(
+        "https://api.spotify.com/v1/playlists/{}/tracks?limit=100",
+        playlist_id
+    ));
+
+    while let Some(url) = next {
+        let (items, page_next) = fetch_items_page(&client, &url, token).await?;
+        tracks.extend(items.into_iter().filter_map(|item| {
+            TrackInfo::from_json(&item["track"], item["added_at"].as_str().unwrap_or_default())
+        }));
+        next = page_next;
+    }
+
+    Some(tracks)
+}
+
+// One page of playlist items, and the URL of the next page if there is one
+async fn fetch_items_page(client: &Client, url: &str, token: &str) -> Option<(Vec<serde_json::Value>, Option<String>)> {
+    let resp = match client
+        .get(url)
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await
+    {
+        Ok(resp) => resp,
+        Err(e) => {
+            log_error(&format!("Request error: {:?}", e));
+            return None;
+        }
+    };
+    if !resp.status().is_success() {
+        log_error(&format!("Failed to fetch playlist tracks: {}", resp.status()));
+        return None;
+    }
+    let json = match resp.json::<serde_json::Value>().await {
+        Ok(json) => json,
+        Err(e) => {
+            log_error(&format!("Failed to parse playlist tracks: {:?}", e));
+            return None;
+        }
+    };
+    let items = json["items"].as_array().cloned().unwrap_or_default();
+    Some((items, json["next"].as_str().map(|url| url.to_string())))
+}
+
+// Downloads a playlist's tracks as a CSV file named after it
+pub async fn export_playlist(playlist_id: String, name: String, token: String) {
+    let tracks = match fetch_all_playlist_tracks(&playlist_id, &token).await {
+        Some(tracks) => tracks,
+        None => return,
+    };
+
+    let mut csv = String::from("Name,Artists,Album,Duration (ms),Added At,Spotify URI\n");
+    for track in &tracks {
+        let fields = [
+            track.name.clone(),
+            track.artists.clone(),
+            track.album.clone(),
+            track.duration_ms.to_string(),
+            track.added_at.clone(),
+            track.uri.clone(),
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+
+    // Keep the filename to characters every OS accepts
+    let filename: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == ' ' || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    let filename = match filename.trim() {
+        "" => "playlist".to_string(),
+        trimmed => trimmed.to_string(),
+    };
+    download_file(csv.as_bytes(), &format!("{}.csv", filename));
+}
+
+// Quotes a CSV field when it contains a separator, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -1,6 +1,7 @@
 use reqwest::Client;
 use crate::ui::APP_STATE;
 use crate::utils::log_error;
+use super::models::PlaylistInfo;
 
 pub async fn fetch_playlists(token: String) {
     let client = Client::new();
@@ -14,23 +15,11 @@ pub async fn fetch_playlists(token: String) {
     {
         if resp.status().is_success() {
             if let Ok(json) = resp.json::<serde_json::Value>().await {
-                let items_value = json["items"].clone();
-                let items_vec = items_value.as_array().cloned().unwrap_or_default();
-                let mut playlists_data = vec![];
-                for item in &items_vec {
-                    let images_value = item["images"].clone();
-                    let images_vec = images_value.as_array().cloned().unwrap_or_default();
-                    let name = item["name"].as_str().unwrap_or("").to_string();
-                    let owner = item["owner"]["display_name"].as_str().unwrap_or("").to_string();
-                    let image_url = images_vec
-                        .get(0)
-                        .and_then(|img| img["url"].as_str())
-                        .unwrap_or("")
-                        .to_string();
-                    let id = item["id"].as_str().unwrap_or("").to_string();
-                    let total_tracks = item["tracks"]["total"].as_i64().unwrap_or(0) as i32;
-                    playlists_data.push((name, owner, image_url, id, total_tracks));
-                }
+                let items = json["items"].as_array().cloned().unwrap_or_default();
+                let playlists_data: Vec<PlaylistInfo> = items
+                    .iter()
+                    .filter_map(PlaylistInfo::from_json)
+                    .collect();
                 let mut state = APP_STATE.lock().unwrap();
                 state.playlists = playlists_data;
                 state.show_playlists = true;
@@ -0,0 +1,124 @@
+use std::sync::Arc;
+use reqwest::Client;
+use serde_json::Value;
+use crate::api_request::models::TrackInfo;
+use crate::ui::APP_STATE;
+use crate::ui::app_state::{CatalogPage, SearchCategory};
+use crate::utils::log_error;
+
+pub const SEARCH_PAGE_SIZE: u32 = 20;
+
+// Searches the Spotify catalog. A new query fetches the first page of every category;
+// paging passes the category so only its page is replaced
+pub async fn search_catalog(token: String, query: String, category: Option<SearchCategory>, offset: u32) {
+    let categories = match category {
+        Some(category) => vec![category],
+        None => SearchCategory::ALL.to_vec(),
+    };
+    let types = categories
+        .iter()
+        .map(|category| category.api_type())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    APP_STATE.lock().unwrap().catalog.loading = true;
+
+    let client = Client::new();
+    let response = client
+        .get("https://api.spotify.com/v1/search")
+        .query(&[
+            ("q", query.as_str()),
+            ("type", types.as_str()),
+            ("limit", &SEARCH_PAGE_SIZE.to_string()),
+            ("offset", &offset.to_string()),
+        ])
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await;
+
+    let json = match response {
+        Ok(resp) if resp.status().is_success() => resp.json::<Value>().await.ok(),
+        Ok(resp) => {
+            log_error(&format!("Failed to search catalog: {}", resp.status()));
+            None
+        }
+        Err(_) => {
+            log_error("Request error while searching catalog.");
+            None
+        }
+    };
+
+    let mut state = APP_STATE.lock().unwrap();
+    // A newer search may have started while this one was in flight
+    if state.catalog.query != query {
+        return;
+    }
+    state.catalog.loading = false;
+    let json = match json {
+        Some(json) => json,
+        None => return,
+    };
+
+    for category in categories {
+        let results = &json[format!("{}s", category.api_type())];
+        // Search results can contain nulls, which the filter_maps skip
+        let items = results["items"].as_array().cloned().unwrap_or_default();
+        let items: Vec<TrackInfo> = match category {
+            SearchCategory::Tracks => items
+                .iter()
+                .filter_map(|track| TrackInfo::from_json(track, ""))
+                .collect(),
+            SearchCategory::Albums => items
+                .iter()
+                .filter_map(|album| Some(TrackInfo {
+                    name: album["name"].as_str()?.to_string(),
+                    artists: joined_artist_names(album),
+                    image_url: first_image_url(album),
+                    uri: album["uri"].as_str()?.to_string(),
+                    release_date: album["release_date"].as_str().unwrap_or_default().to_string(),
+                    ..Default::default()
+                }))
+                .collect(),
+            SearchCategory::Artists => items
+                .iter()
+                .filter_map(|artist| Some(TrackInfo {
+                    name: artist["name"].as_str()?.to_string(),
+                    artists: artist["genres"].as_array()
+                        .map(|genres| genres.iter().filter_map(|g| g.as_str()).take(3).collect::<Vec<_>>().join(", "))
+                        .unwrap_or_default(),
+                    image_url: first_image_url(artist),
+                    uri: artist["uri"].as_str()?.to_string(),
+                    popularity: artist["popularity"].as_i64().unwrap_or_default() as i32,
+                    ..Default::default()
+                }))
+                .collect(),
+            // Playlists keep their ID in uri, as the playlist views expect
+            SearchCategory::Playlists => items
+                .iter()
+                .filter_map(|playlist| Some(TrackInfo {
+                    name: playlist["name"].as_str()?.to_string(),
+                    artists: playlist["owner"]["display_name"].as_str().unwrap_or_default().to_string(),
+                    image_url: first_image_url(playlist),
+                    uri: playlist["id"].as_str()?.to_string(),
+                    ..Default::default()
+                }))
+                .collect(),
+        };
+
+        *state.catalog.page_mut(category) = CatalogPage {
+            items: Arc::new(items),
+            offset,
+            total: results["total"].as_u64().unwrap_or_default() as u32,
+        };
+    }
+}
+
+fn joined_artist_names(item: &Value) -> String {
+    item["artists"].as_array()
+        .map(|artists| artists.iter().filter_map(|a| a["name"].as_str()).collect::<Vec<_>>().join(", "))
+        .unwrap_or_default()
+}
+
+fn first_image_url(item: &Value) -> String {
+    item["images"][0]["url"].as_str().unwrap_or_default().to_string()
+}
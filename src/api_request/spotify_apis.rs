@@ -41,6 +41,7 @@ where
             let _ = js_sys::Reflect::set(&window, &"totalDuration".into(), &0.0.into());
             let _ = js_sys::Reflect::set(&window, &"isPlaying".into(), &false.into());
         }
+        crate::mediaplayer::media_session::sync_media_session();
     };
 
     match response {
@@ -1,55 +1,48 @@
+use std::collections::HashMap;
 use image::{DynamicImage, ImageBuffer, RgbaImage};
-use std::cmp::Ordering;
-use super::color_analysis::*;
+use serde::{Deserialize, Serialize};
+use super::color_analysis::cached_color_stats;
+use super::color_sort::sort_by_color;
+use super::styling::{background, draw_caption, draw_shadow, CollageStyle};
+use super::layouts::{color_wheel_layout, cut_tile, grid_layout, hexagonal_layout, mixed_layout, photomosaic_layout, spiral_layout, TileShape};
 use wasm_bindgen::prelude::*;
-use crate::ui::app_state::{GradientDirection, StartingCorner};
-
-pub fn create_collage(images: Vec<DynamicImage>, width: u32, height: u32, color_shift: f32, gradient_direction: GradientDirection, starting_corner: StartingCorner) -> Result<DynamicImage, JsValue> {
-    // Separate images into black, white, desaturated, and colored based on thresholds
-    let mut black_images = Vec::new();
-    let mut white_images = Vec::new();
-    let mut desaturated_images = Vec::new();
-    let mut colored_images = Vec::new();
-    
-    for img in images {
-        let black_percentage = calculate_black_percentage(&img);
-        let white_percentage = calculate_white_percentage(&img);
-        let desaturation_percentage = calculate_desaturation_percentage(&img);
-        
-        if black_percentage > 60.0 {
-            black_images.push(img);
-        } else if white_percentage > 60.0 { // Prioritize white images over desaturated
-            white_images.push(img);
-        } else if desaturation_percentage > 60.0 {
-            desaturated_images.push(img);
-        } else {
-            colored_images.push(img);
+use crate::ui::app_state::{CollageLayout, ColorSort, GradientDirection, StartingCorner};
+
+const YIELD_INTERVAL_MS: f64 = 50.0;
+
+// Counts the per-image steps of a collage and hands control back to the event loop every so often,
+// so a worker can receive cancel messages (and the page keeps drawing when there's no worker)
+pub struct Progress<'a> {
+    on_progress: &'a mut dyn FnMut(usize, usize) -> bool, // Returns false to stop the collage
+    done: usize,
+    total: usize,
+    last_yield: f64,
+}
+
+impl Progress<'_> {
+    async fn step(&mut self) -> Result<(), JsValue> {
+        self.done += 1;
+        self.pause().await
+    }
+
+    // Checks for a cancel and yields if it's been a while, without counting a step. For long
+    // stretches of work between steps
+    pub async fn pause(&mut self) -> Result<(), JsValue> {
+        if !(self.on_progress)(self.done, self.total) {
+            return Err(JsValue::from_str("Collage cancelled"));
+        }
+        let now = js_sys::Date::now();
+        if now - self.last_yield > YIELD_INTERVAL_MS {
+            gloo_timers::future::TimeoutFuture::new(0).await;
+            self.last_yield = js_sys::Date::now();
         }
+        Ok(())
     }
+}
 
-    // Sort colored images by hue with hue shifting
-    let mut images_with_hue: Vec<(DynamicImage, f32)> = colored_images.into_iter()
-        .map(|img| {
-            let hue = calculate_dominant_hue(&img);
-            // Shift hue by user-selected degrees
-            let shifted_hue = (hue + color_shift) % 360.0;
-            (img, shifted_hue)
-        })
-        .collect();
-
-    images_with_hue.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
-
-    let sorted_colored_images: Vec<DynamicImage> = images_with_hue.into_iter()
-        .map(|(img, _)| img)
-        .collect();
-
-    // Combine images in the order: colored_images, white_images, desaturated_images, black_images
-    let mut final_images = sorted_colored_images;
-    final_images.extend(white_images);
-    final_images.extend(desaturated_images);
-    final_images.extend(black_images);
-
-    let num_images = final_images.len();
+// Columns, rows and tile size of the grid that fits `count` square tiles closest to the requested
+// aspect ratio. Callers use the tile size to pick which artwork resolution to download
+pub fn grid_dimensions(count: usize, width: u32, height: u32) -> (u32, u32, u32) {
     let aspect_ratio = width as f32 / height as f32;
 
     // Determine optimal number of columns and rows
@@ -57,8 +50,8 @@ pub fn create_collage(images: Vec<DynamicImage>, width: u32, height: u32, color_
     let mut best_cols = 1;
     let mut min_diff = f32::INFINITY;
 
-    for rows in 1..=num_images as u32 {
-        let cols = (num_images as f32 / rows as f32).ceil() as u32;
+    for rows in 1..=count as u32 {
+        let cols = (count as f32 / rows as f32).ceil() as u32;
         let current_aspect = cols as f32 / rows as f32;
         let diff = (current_aspect - aspect_ratio).abs();
 
@@ -70,109 +63,166 @@ pub fn create_collage(images: Vec<DynamicImage>, width: u32, height: u32, color_
     }
 
     let tile_size = (width / best_cols).min(height / best_rows);
-    let collage_width = tile_size * best_cols;
-    let collage_height = tile_size * best_rows;
-
-    let mut collage: RgbaImage = ImageBuffer::new(collage_width, collage_height);
-
-    // Generate positions based on gradient direction and starting corner
-    let mut positions = Vec::new();
-    match gradient_direction {
-        GradientDirection::Diagonal => {
-            for s in 0..(best_rows + best_cols - 1) {
-                for row in 0..best_rows {
-                    if s >= row {
-                        let col = s - row;
-                        if col < best_cols {
-                            positions.push((col, row));
-                        }
-                    }
-                }
-            }
-        },
-        GradientDirection::Vertical => { // Swapped with Horizontal
-            for row in 0..best_rows {
-                for col in 0..best_cols {
-                    positions.push((col, row));
-                }
-            }
-        },
-        GradientDirection::Horizontal => { // Swapped with Vertical
-            for col in 0..best_cols {
-                for row in 0..best_rows {
-                    positions.push((col, row));
-                }
-            }
-        },
+    (best_cols, best_rows, tile_size)
+}
+
+// Columns, rows and tile size of a photomosaic of a `target_width` × `target_height` picture. It
+// keeps the picture's proportions and fits inside width × height
+pub fn mosaic_dimensions(target_width: u32, target_height: u32, columns: u32, width: u32, height: u32) -> (u32, u32, u32) {
+    let cols = columns.max(1);
+    let rows = (target_height as f32 / target_width.max(1) as f32 * cols as f32).round().max(1.0) as u32;
+    let tile_size = (width / cols).min(height / rows).max(1);
+    (cols, rows, tile_size)
+}
+
+// Everything about how a collage looks besides the artwork itself
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CollageSettings {
+    pub width: u32,
+    pub height: u32,
+    pub hue_shift: f32,
+    pub gradient_direction: GradientDirection,
+    pub starting_corner: StartingCorner,
+    pub color_sort: ColorSort,
+    pub layout: CollageLayout,
+    pub mosaic: MosaicSettings,
+    pub style: CollageStyle,
+}
+
+// How the photomosaic layout recreates its picture
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MosaicSettings {
+    pub columns: u32, // Cells across the picture; rows follow its proportions
+    pub reuse_limit: usize, // Times a cover may be used, 0 for no limit
+    pub tint: f32, // How far each tile is blended towards its cell's color, from 0 to 1
+}
+
+impl Default for MosaicSettings {
+    fn default() -> Self {
+        Self { columns: 40, reuse_limit: 0, tint: 0.25 }
     }
+}
 
-    // Adjust positions based on starting corner or side
-    match gradient_direction {
-        GradientDirection::Diagonal => match starting_corner {
-            StartingCorner::TopLeft => {},
-            StartingCorner::TopRight => {
-                for pos in &mut positions {
-                    pos.0 = best_cols - 1 - pos.0;
-                }
-            },
-            StartingCorner::BottomLeft => {
-                for pos in &mut positions {
-                    pos.1 = best_rows - 1 - pos.1;
-                }
-            },
-            StartingCorner::BottomRight => {
-                for pos in &mut positions {
-                    pos.0 = best_cols - 1 - pos.0;
-                    pos.1 = best_rows - 1 - pos.1;
-                }
-            },
-        },
-        GradientDirection::Vertical => match starting_corner { // Swapped with Horizontal
-            StartingCorner::TopLeft => {}, // Left
-            StartingCorner::TopRight => { // Right
-                for pos in &mut positions {
-                    pos.0 = best_cols - 1 - pos.0;
-                }
-            },
-            _ => {},
-        },
-        GradientDirection::Horizontal => match starting_corner { // Swapped with Vertical
-            StartingCorner::TopLeft => {}, // Top
-            StartingCorner::BottomLeft => { // Bottom
-                for pos in &mut positions {
-                    pos.1 = best_rows - 1 - pos.1;
-                }
-            },
-            _ => {},
-        },
+// One tile's worth of artwork. Repeated artwork comes as several of these
+pub struct CollageImage {
+    pub url: String, // Keys the cached color stats
+    pub image: DynamicImage,
+    pub track_count: usize, // Tracks using the artwork, which picks featured covers
+}
+
+// Where the tiles ended up in the finished collage, so exports can link them to what they show
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TileMap {
+    pub shape: TileShape,
+    pub regions: Vec<TileRegion>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TileRegion {
+    pub image: usize, // Index into the images the collage was made from
+    pub x: i64,
+    pub y: i64,
+    pub size: u32,
+}
+
+// Each image is analysed and each tile drawn, and `on_progress` hears about both steps. The
+// photomosaic layout recreates `mosaic_target`
+pub async fn create_collage(
+    images: Vec<CollageImage>,
+    settings: &CollageSettings,
+    mosaic_target: Option<&DynamicImage>,
+    on_progress: &mut dyn FnMut(usize, usize) -> bool,
+) -> Result<(DynamicImage, TileMap), JsValue> {
+    let mut progress = Progress { on_progress, done: 0, total: images.len() * 2, last_yield: js_sys::Date::now() };
+
+    let mut stats = Vec::with_capacity(images.len());
+    for image in &images {
+        stats.push(cached_color_stats(&image.url, &image.image));
+        progress.step().await?;
     }
 
-    // If there are more images than positions, expand the grid
-    let current_rows = best_rows;
-    let mut current_cols = best_cols;
-    while positions.len() < final_images.len() {
-        current_cols += 1;
-        for row in 0..current_rows {
-            let col = current_cols - 1 - row;
-            if col < current_cols {
-                positions.push((col, row));
-            }
+    // Tiles are laid out in what's left after the gap around the edge, then shrunk by the gap
+    let style = &settings.style;
+    let gap = style.gap;
+    let inner = CollageSettings {
+        width: settings.width.saturating_sub(gap).max(1),
+        height: settings.height.saturating_sub(gap).max(1),
+        ..settings.clone()
+    };
+    let urls: Vec<String> = images.iter().map(|image| image.url.clone()).collect();
+    let order = sort_by_color(&stats, settings.color_sort, settings.hue_shift);
+    let layout = match settings.layout {
+        CollageLayout::Grid => grid_layout(&stats, &order, &inner, &mut progress).await?,
+        CollageLayout::Spiral => spiral_layout(&order, inner.width, inner.height),
+        CollageLayout::Mixed => {
+            let track_counts: Vec<usize> = images.iter().map(|image| image.track_count).collect();
+            mixed_layout(&order, &urls, &track_counts, inner.width, inner.height)
         }
-        if positions.len() >= final_images.len() {
-            break;
+        CollageLayout::Hexagonal => hexagonal_layout(&order, inner.width, inner.height),
+        CollageLayout::ColorWheel => color_wheel_layout(&stats, settings.hue_shift, inner.width, inner.height),
+        CollageLayout::Photomosaic => {
+            let target = mosaic_target.ok_or_else(|| JsValue::from_str("Choose a picture for the photomosaic"))?;
+            photomosaic_layout(&stats, &urls, target, &settings.mosaic, inner.width, inner.height)
         }
+    };
+    progress.total = images.len() + layout.tiles.len();
+
+    // Padding centers the tiles on a canvas of exactly the requested size
+    let (tiles_width, tiles_height) = (layout.width + gap, layout.height + gap);
+    let (width, height) = if style.fill_size {
+        (settings.width.max(tiles_width), settings.height.max(tiles_height))
+    } else {
+        (tiles_width, tiles_height)
+    };
+    let offset_x = ((width - tiles_width) / 2 + gap) as i64;
+    let offset_y = ((height - tiles_height) / 2 + gap) as i64;
+
+    // Images used for several tiles of one size are only resized once
+    let mut resized_tiles: HashMap<(usize, u32), RgbaImage> = HashMap::new();
+    let mut tiles_layer: RgbaImage = ImageBuffer::new(width, height);
+    let mut largest_tile = 0;
+    let mut regions = Vec::with_capacity(layout.tiles.len());
+    for (image, placement) in layout.tiles {
+        let size = placement.size.saturating_sub(gap).max(1);
+        largest_tile = largest_tile.max(size);
+        let resized = resized_tiles.entry((image, size)).or_insert_with(|| {
+            let mut resized = images[image].image.resize_exact(size, size, image::imageops::FilterType::Nearest).to_rgba8();
+            if layout.shape != TileShape::Square || style.corner_radius > 0.0 {
+                cut_tile(&mut resized, layout.shape, style.corner_radius);
+            }
+            resized
+        });
+        let (x, y) = (placement.x + offset_x, placement.y + offset_y);
+        regions.push(TileRegion { image, x, y, size });
+        match placement.tint {
+            Some(tint) => {
+                let mut tinted = resized.clone();
+                tint_tile(&mut tinted, tint, settings.mosaic.tint);
+                image::imageops::overlay(&mut tiles_layer, &tinted, x, y);
+            }
+            None => image::imageops::overlay(&mut tiles_layer, resized, x, y),
+        }
+        progress.step().await?;
+    }
+
+    let mut collage = background(style, &tiles_layer);
+    if style.shadow {
+        draw_shadow(&mut collage, &tiles_layer, largest_tile);
+    }
+    image::imageops::overlay(&mut collage, &tiles_layer, 0, 0);
+    if !style.caption.is_empty() {
+        draw_caption(&mut collage, &style.caption, style.caption_position);
     }
 
-    // Assign images to positions in the specified order
-    for (i, img) in final_images.into_iter().enumerate() {
-        if let Some((col, row)) = positions.get(i) {
-            let x = (*col * tile_size) as i64;
-            let y = (*row * tile_size) as i64;
+    Ok((DynamicImage::ImageRgba8(collage), TileMap { shape: layout.shape, regions }))
+}
 
-            let resized = img.resize_exact(tile_size, tile_size, image::imageops::FilterType::Nearest).to_rgba8();
-            image::imageops::overlay(&mut collage, &resized, x, y);
+// Blends a tile towards a color, by `amount` from 0 to 1
+fn tint_tile(tile: &mut RgbaImage, tint: [u8; 3], amount: f32) {
+    let amount = amount.clamp(0.0, 1.0);
+    for pixel in tile.pixels_mut() {
+        for (channel, target) in pixel.0.iter_mut().zip(tint) {
+            *channel = (*channel as f32 * (1.0 - amount) + target as f32 * amount).round() as u8;
         }
     }
-
-    Ok(DynamicImage::ImageRgba8(collage))
 }
@@ -1,79 +1,150 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
 use image::DynamicImage;
-
-pub fn calculate_black_percentage(img: &DynamicImage) -> f32 {
-    let rgba = img.to_rgba8();
-    let mut black_pixels = 0;
-    let total_pixels = (rgba.width() * rgba.height()) as f32;
-
-    for pixel in rgba.pixels() {
-        let [r, g, b, a] = pixel.0;
-        if a > 0 && r < 30 && g < 30 && b < 30 {
-            black_pixels += 1;
-        }
-    }
-
-    (black_pixels as f32 / total_pixels) * 100.0
+use once_cell::sync::Lazy;
+
+const ANALYSIS_SIZE: u32 = 64; // Images are measured on a copy no bigger than this
+const PALETTE_SIZE: usize = 5;
+const KMEANS_ITERATIONS: usize = 8;
+
+// What the collage needs to know about an image's colors, measured in one pass over its pixels
+#[derive(Clone, Default)]
+pub struct ImageColorStats {
+    pub black_ratio: f32,
+    pub white_ratio: f32,
+    pub desaturated_ratio: f32,
+    pub dominant_hue: f32, // Degrees, weighted towards colorful, bright pixels
+    pub average_lab: [f32; 3], // CIE L*a*b*, L from 0 to 100
+    pub palette: Vec<[u8; 3]>, // k-means clusters of the pixels, the largest (the dominant color) first
 }
 
-pub fn calculate_white_percentage(img: &DynamicImage) -> f32 {
-    let rgba = img.to_rgba8();
-    let mut white_pixels = 0;
-    let total_pixels = (rgba.width() * rgba.height()) as f32;
+// Stats by artwork URL. They don't change with the collage settings, so reruns skip the analysis
+static COLOR_STATS: Lazy<Mutex<HashMap<String, ImageColorStats>>> = Lazy::new(|| Mutex::new(HashMap::new()));
 
-    for pixel in rgba.pixels() {
-        let [r, g, b, a] = pixel.0;
-        if a > 0 && r > 225 && g > 225 && b > 225 {
-            white_pixels += 1;
-        }
+// Stats of the image at `url`, analysing it the first time it's seen. Images without a URL aren't cached
+pub fn cached_color_stats(url: &str, img: &DynamicImage) -> ImageColorStats {
+    if let Some(stats) = COLOR_STATS.lock().unwrap().get(url) {
+        return stats.clone();
     }
-
-    (white_pixels as f32 / total_pixels) * 100.0
+    let stats = ImageColorStats::from_image(img);
+    if !url.is_empty() {
+        COLOR_STATS.lock().unwrap().insert(url.to_string(), stats.clone());
+    }
+    stats
 }
 
-pub fn calculate_desaturation_percentage(img: &DynamicImage) -> f32 {
-    let rgba = img.to_rgba8();
-    let mut desaturated_pixels = 0;
-    let total_pixels = (rgba.width() * rgba.height()) as f32;
-
-    for pixel in rgba.pixels() {
-        let [r, g, b, a] = pixel.0;
-        if a > 0 {
-            let max = r.max(g).max(b) as f32;
-            let min = r.min(g).min(b) as f32;
-            let saturation = if max > 0.0 { (max - min) / max } else { 0.0 };
-            if saturation < 0.2 { // Low saturation threshold
-                desaturated_pixels += 1;
+impl ImageColorStats {
+    pub fn from_image(img: &DynamicImage) -> Self {
+        let rgba = img.thumbnail(ANALYSIS_SIZE, ANALYSIS_SIZE).to_rgba8();
+        let total_pixels = (rgba.width() * rgba.height()).max(1) as f32;
+
+        let mut black_pixels = 0;
+        let mut white_pixels = 0;
+        let mut desaturated_pixels = 0;
+        let mut hue_sum = 0.0;
+        let mut weighted_count = 0.0;
+        let mut lab_sum = [0.0; 3];
+        let mut opaque_pixels = 0;
+        let mut colors = Vec::with_capacity((rgba.width() * rgba.height()) as usize);
+        // Colors quantized to 3 bits per channel: (count, summed color), which seed the clustering
+        let mut buckets: HashMap<u16, (u32, [u32; 3])> = HashMap::new();
+
+        for pixel in rgba.pixels() {
+            let [r, g, b, a] = pixel.0;
+            if a == 0 {
+                continue;
             }
-        }
-    }
-
-    (desaturated_pixels as f32 / total_pixels) * 100.0
-}
+            opaque_pixels += 1;
 
-pub fn calculate_dominant_hue(img: &DynamicImage) -> f32 {
-    let rgba = img.to_rgba8();
-    let mut hue_sum = 0.0;
-    let mut weighted_count = 0.0;
+            if r < 30 && g < 30 && b < 30 {
+                black_pixels += 1;
+            }
+            if r > 225 && g > 225 && b > 225 {
+                white_pixels += 1;
+            }
 
-    for pixel in rgba.pixels() {
-        let [r, g, b, a] = pixel.0;
-        if a > 0 {
             let (h, s, v) = rgb_to_hsv(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+            if s < 0.2 { // Low saturation threshold
+                desaturated_pixels += 1;
+            }
             // Weight by saturation and value to give more importance to colorful, bright pixels
             let weight = s * v;
             hue_sum += h * weight;
             weighted_count += weight;
+
+            let lab = rgb_to_lab(r, g, b);
+            for (sum, value) in lab_sum.iter_mut().zip(lab) {
+                *sum += value;
+            }
+            colors.push(([r as f32, g as f32, b as f32], lab));
+
+            let key = ((r as u16 >> 5) << 6) | ((g as u16 >> 5) << 3) | (b as u16 >> 5);
+            let bucket = buckets.entry(key).or_insert((0, [0; 3]));
+            bucket.0 += 1;
+            bucket.1[0] += r as u32;
+            bucket.1[1] += g as u32;
+            bucket.1[2] += b as u32;
+        }
+
+        let palette = kmeans_palette(&colors, buckets.into_values().collect());
+
+        let opaque = opaque_pixels.max(1) as f32;
+        Self {
+            black_ratio: black_pixels as f32 / total_pixels,
+            white_ratio: white_pixels as f32 / total_pixels,
+            desaturated_ratio: desaturated_pixels as f32 / total_pixels,
+            dominant_hue: if weighted_count > 0.0 { hue_sum / weighted_count } else { 0.0 },
+            average_lab: lab_sum.map(|sum| sum / opaque),
+            palette,
         }
     }
+}
 
-    if weighted_count > 0.0 {
-        hue_sum / weighted_count
-    } else {
-        0.0
+// Clusters the pixels' (RGB, Lab) colors in Lab space, starting from the most common quantized
+// colors. The image is already downsampled, so that's a few thousand pixels at most
+fn kmeans_palette(colors: &[([f32; 3], [f32; 3])], mut buckets: Vec<(u32, [u32; 3])>) -> Vec<[u8; 3]> {
+    buckets.sort_by_key(|(count, _)| std::cmp::Reverse(*count));
+    let mut centroids: Vec<([f32; 3], [f32; 3])> = buckets
+        .iter()
+        .take(PALETTE_SIZE)
+        .map(|(count, sum)| {
+            let rgb = sum.map(|channel| (channel / count) as f32);
+            (rgb, rgb_to_lab(rgb[0] as u8, rgb[1] as u8, rgb[2] as u8))
+        })
+        .collect();
+
+    let mut sizes = vec![0; centroids.len()];
+    for _ in 0..KMEANS_ITERATIONS {
+        let mut sums = vec![[0.0; 3]; centroids.len()];
+        sizes = vec![0; centroids.len()];
+        for (rgb, lab) in colors {
+            let closest = (0..centroids.len())
+                .min_by(|&a, &b| lab_distance(&centroids[a].1, lab).partial_cmp(&lab_distance(&centroids[b].1, lab)).unwrap_or(std::cmp::Ordering::Equal))
+                .unwrap_or_default();
+            sizes[closest] += 1;
+            for (sum, channel) in sums[closest].iter_mut().zip(rgb) {
+                *sum += channel;
+            }
+        }
+        for ((centroid, sum), size) in centroids.iter_mut().zip(&sums).zip(&sizes) {
+            if *size > 0 {
+                let rgb = sum.map(|channel| channel / *size as f32);
+                *centroid = (rgb, rgb_to_lab(rgb[0] as u8, rgb[1] as u8, rgb[2] as u8));
+            }
+        }
     }
+
+    let mut clusters: Vec<(u32, [f32; 3])> = sizes.into_iter().zip(centroids).map(|(size, (rgb, _))| (size, rgb)).collect();
+    clusters.retain(|(size, _)| *size > 0);
+    clusters.sort_by_key(|(size, _)| std::cmp::Reverse(*size));
+    clusters.into_iter().map(|(_, rgb)| rgb.map(|channel| channel as u8)).collect()
 }
 
-fn rgb_to_hsv(r: f32, g: f32, b: f32) -> (f32, f32, f32) {
+fn lab_distance(a: &[f32; 3], b: &[f32; 3]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| (x - y).powi(2)).sum()
+}
+
+pub fn rgb_to_hsv(r: f32, g: f32, b: f32) -> (f32, f32, f32) {
     let max = r.max(g).max(b);
     let min = r.min(g).min(b);
     let delta = max - min;
@@ -94,3 +165,20 @@ fn rgb_to_hsv(r: f32, g: f32, b: f32) -> (f32, f32, f32) {
 
     (hue, saturation, value)
 }
+
+// sRGB to CIE L*a*b* under a D65 white point
+pub fn rgb_to_lab(r: u8, g: u8, b: u8) -> [f32; 3] {
+    let linear = |c: u8| {
+        let c = c as f32 / 255.0;
+        if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    };
+    let (r, g, b) = (linear(r), linear(g), linear(b));
+
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+
+    let f = |t: f32| if t > 0.008856 { t.cbrt() } else { 7.787 * t + 16.0 / 116.0 };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
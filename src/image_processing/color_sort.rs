@@ -0,0 +1,161 @@
+use std::cmp::Ordering;
+use super::collage::Progress;
+use super::color_analysis::{rgb_to_hsv, rgb_to_lab, ImageColorStats};
+use crate::ui::app_state::ColorSort;
+use wasm_bindgen::JsValue;
+
+const HUE_BANDS: f32 = 12.0; // 30° bands for hue-then-lightness
+const SOM_EPOCHS: usize = 12;
+
+// Orders images for a gradient layout. Returns indices into `stats`
+pub fn sort_by_color(stats: &[ImageColorStats], sort: ColorSort, hue_shift: f32) -> Vec<usize> {
+    let shifted = |hue: f32| (hue + hue_shift) % 360.0;
+    match sort {
+        ColorSort::Hue => {
+            // Colored images by hue, then mostly white, desaturated and black ones
+            let group = |s: &ImageColorStats| {
+                if s.black_ratio > 0.6 {
+                    3
+                } else if s.white_ratio > 0.6 { // Prioritize white images over desaturated
+                    1
+                } else if s.desaturated_ratio > 0.6 {
+                    2
+                } else {
+                    0
+                }
+            };
+            sorted_indices(stats, |a, b| {
+                group(a).cmp(&group(b)).then_with(|| {
+                    if group(a) == 0 { compare(shifted(a.dominant_hue), shifted(b.dominant_hue)) } else { Ordering::Equal }
+                })
+            })
+        }
+        ColorSort::DominantColor => {
+            // Colorful covers by the hue of their main color, so a cover half red and half blue isn't
+            // filed as purple. Gray ones follow, light to dark
+            sorted_indices(stats, |a, b| match (dominant_hue(a), dominant_hue(b)) {
+                (Some(hue_a), Some(hue_b)) => compare(shifted(hue_a), shifted(hue_b)),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => compare(lightness(b), lightness(a)),
+            })
+        }
+        ColorSort::Lightness => sorted_indices(stats, |a, b| compare(lightness(b), lightness(a))),
+        ColorSort::HueThenLightness => {
+            let band = |s: &ImageColorStats| dominant_hue(s).map(|hue| (shifted(hue) / (360.0 / HUE_BANDS)) as u32);
+            sorted_indices(stats, |a, b| match (band(a), band(b)) {
+                (Some(band_a), Some(band_b)) => band_a.cmp(&band_b).then_with(|| compare(lightness(b), lightness(a))),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => compare(lightness(b), lightness(a)),
+            })
+        }
+        // The grid places these itself; other layouts need a line, for which lightness is the closest
+        ColorSort::SelfOrganizing => sorted_indices(stats, |a, b| compare(lightness(b), lightness(a))),
+    }
+}
+
+fn sorted_indices(stats: &[ImageColorStats], cmp: impl Fn(&ImageColorStats, &ImageColorStats) -> Ordering) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..stats.len()).collect();
+    indices.sort_by(|&a, &b| cmp(&stats[a], &stats[b]));
+    indices
+}
+
+fn compare(a: f32, b: f32) -> Ordering {
+    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+}
+
+fn lightness(stats: &ImageColorStats) -> f32 {
+    stats.average_lab[0]
+}
+
+// Hue of the largest color cluster, or None when that color is too gray or dark to have a useful hue
+pub fn dominant_hue(stats: &ImageColorStats) -> Option<f32> {
+    let [r, g, b] = *stats.palette.first()?;
+    let (h, s, v) = rgb_to_hsv(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+    (s >= 0.2 && v >= 0.15).then_some(h)
+}
+
+// Places images on a cols × rows grid with a self-organizing map, so covers with similar colors end
+// up next to each other both across and down. Returns the (col, row) of each image. Each epoch
+// compares every image with every cell, so `progress` gets a chance to cancel between them
+pub async fn self_organizing_layout(stats: &[ImageColorStats], cols: u32, rows: u32, progress: &mut Progress<'_>) -> Result<Vec<(u32, u32)>, JsValue> {
+    // Each image is described by its main color and its average color, both in Lab
+    let features: Vec<[f32; 6]> = stats
+        .iter()
+        .map(|s| {
+            let [r, g, b] = s.palette.first().copied().unwrap_or_default();
+            let [l1, a1, b1] = rgb_to_lab(r, g, b);
+            let [l2, a2, b2] = s.average_lab;
+            [l1, a1, b1, l2, a2, b2]
+        })
+        .collect();
+    let cell_count = (cols * rows) as usize;
+    if features.is_empty() || cell_count == 0 {
+        return Ok(Vec::new());
+    }
+
+    // Nodes start out in lightness order, which the training then bends into two dimensions
+    let by_lightness = sorted_indices(stats, |a, b| compare(lightness(b), lightness(a)));
+    let mut nodes: Vec<[f32; 6]> = (0..cell_count)
+        .map(|cell| features[by_lightness[cell * features.len() / cell_count]])
+        .collect();
+
+    let start_radius = (cols.max(rows) as f32 / 2.0).max(1.0);
+    let total_steps = (SOM_EPOCHS * features.len()) as f32;
+    let mut step = 0.0;
+    for _ in 0..SOM_EPOCHS {
+        for feature in &features {
+            let trained = step / total_steps;
+            let radius = start_radius * (1.0 - trained) + 0.5 * trained;
+            let learning_rate = 0.5 * (1.0 - trained) + 0.01;
+            let winner = nearest(&nodes, feature);
+            let (winner_col, winner_row) = ((winner as u32 % cols) as f32, (winner as u32 / cols) as f32);
+
+            for (cell, node) in nodes.iter_mut().enumerate() {
+                let (col, row) = ((cell as u32 % cols) as f32, (cell as u32 / cols) as f32);
+                let grid_distance = (col - winner_col).powi(2) + (row - winner_row).powi(2);
+                if grid_distance > radius * radius * 4.0 {
+                    continue;
+                }
+                let influence = learning_rate * (-grid_distance / (2.0 * radius * radius)).exp();
+                for (weight, value) in node.iter_mut().zip(feature) {
+                    *weight += influence * (value - *weight);
+                }
+            }
+            step += 1.0;
+        }
+        progress.pause().await?;
+    }
+
+    // Every image gets its own cell: the closest matches choose first, the rest take the nearest free cell
+    let mut order: Vec<(usize, f32)> = features
+        .iter()
+        .enumerate()
+        .map(|(image, feature)| (image, distance(&nodes[nearest(&nodes, feature)], feature)))
+        .collect();
+    order.sort_by(|a, b| compare(a.1, b.1));
+
+    let mut taken = vec![false; cell_count];
+    let mut cells = vec![(0, 0); features.len()];
+    for (image, _) in order {
+        let free = (0..cell_count)
+            .filter(|&cell| !taken[cell])
+            .min_by(|&a, &b| compare(distance(&nodes[a], &features[image]), distance(&nodes[b], &features[image])));
+        if let Some(cell) = free {
+            taken[cell] = true;
+            cells[image] = (cell as u32 % cols, cell as u32 / cols);
+        }
+    }
+    Ok(cells)
+}
+
+fn nearest(nodes: &[[f32; 6]], feature: &[f32; 6]) -> usize {
+    (0..nodes.len())
+        .min_by(|&a, &b| compare(distance(&nodes[a], feature), distance(&nodes[b], feature)))
+        .unwrap_or_default()
+}
+
+fn distance(a: &[f32; 6], b: &[f32; 6]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| (x - y).powi(2)).sum()
+}
@@ -0,0 +1,32 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::codecs::jpeg::JpegEncoder;
+use image::DynamicImage;
+
+const MAX_COVER_BYTES: usize = 256 * 1024; // Spotify's limit on the base64 encoded upload
+const COVER_SIZE: u32 = 640; // Largest size Spotify shows playlist covers at
+const MIN_COVER_SIZE: u32 = 64;
+
+// Encodes a square image as a base64 JPEG playlist cover, bringing the quality and then the size
+// down until it fits under Spotify's limit. Other shapes aren't cropped but refused
+pub fn encode_playlist_cover(image: &DynamicImage) -> Option<String> {
+    if image.width() != image.height() {
+        return None;
+    }
+
+    let mut size = image.width().min(COVER_SIZE);
+    while size >= MIN_COVER_SIZE {
+        let pixels = image.resize_exact(size, size, image::imageops::FilterType::Triangle).to_rgb8();
+        for quality in [90, 80, 70, 60, 50] {
+            let mut jpeg = Vec::new();
+            if JpegEncoder::new_with_quality(&mut jpeg, quality).encode_image(&pixels).is_err() {
+                return None;
+            }
+            let encoded = STANDARD.encode(&jpeg);
+            if encoded.len() <= MAX_COVER_BYTES {
+                return Some(encoded);
+            }
+        }
+        size = size * 3 / 4;
+    }
+    None
+}
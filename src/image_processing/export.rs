@@ -0,0 +1,283 @@
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::{DynamicImage, ImageEncoder};
+use serde_json::json;
+use super::collage::TileMap;
+use super::layouts::TileShape;
+use crate::ui::app_state::ExportFormat;
+use crate::ui::track_query::fold_diacritic;
+
+const MAX_DESCRIPTION_BYTES: usize = 60 * 1024; // JPEG keeps Exif in one segment of at most 64 KiB
+const PNG_TEXT_KEYWORD: &str = "Description";
+const SOFTWARE: &str = "spotify-egui";
+
+// What a finished collage shows, kept with it for the export's metadata and image map
+#[derive(Clone)]
+pub struct CollageDetails {
+    pub source: String, // Summary of the sources, like "Liked Songs" or "3 sources"
+    pub tracks: Vec<String>, // "Artists - Title" of every track
+    pub artworks: Vec<(String, String)>, // (name, album or artist URI) of each image the tile map refers to
+    pub tiles: TileMap,
+}
+
+impl CollageDetails {
+    // Base name of the downloads, like "collage-liked-songs-1920x1080"
+    pub fn file_stem(&self, width: u32, height: u32) -> String {
+        let mut slug = String::new();
+        for c in self.source.to_lowercase().chars() {
+            if c.is_alphanumeric() {
+                slug.push(c);
+            } else if !slug.is_empty() && !slug.ends_with('-') {
+                slug.push('-');
+            }
+        }
+        let slug = slug.trim_end_matches('-');
+        if slug.is_empty() {
+            format!("collage-{}x{}", width, height)
+        } else {
+            format!("collage-{}-{}x{}", slug, width, height)
+        }
+    }
+
+    // The track listing embedded in the image, cut short if it's longer than `max_bytes`
+    fn description(&self, max_bytes: usize) -> String {
+        let mut description = format!("Spotify collage of {}, {} tracks:", self.source, self.tracks.len());
+        for (index, track) in self.tracks.iter().enumerate() {
+            if description.len() + track.len() + 1 > max_bytes {
+                description.push_str(&format!("\n...and {} more", self.tracks.len() - index));
+                break;
+            }
+            description.push('\n');
+            description.push_str(track);
+        }
+        description
+    }
+
+    // Each tile with the area it covers and where it links to
+    pub fn image_map_json(&self, image_file: &str, width: u32, height: u32) -> String {
+        let tiles: Vec<_> = self
+            .tiles
+            .regions
+            .iter()
+            .filter_map(|region| {
+                let (name, uri) = self.artworks.get(region.image)?;
+                Some(json!({
+                    "x": region.x,
+                    "y": region.y,
+                    "size": region.size,
+                    "name": name,
+                    "uri": spotify_uri(uri),
+                    "url": open_url(uri),
+                }))
+            })
+            .collect();
+        let shape = match self.tiles.shape {
+            TileShape::Square => "square",
+            TileShape::Hexagon => "hexagon",
+            TileShape::Circle => "circle",
+        };
+        let map = json!({ "image": image_file, "width": width, "height": height, "shape": shape, "tiles": tiles });
+        serde_json::to_string_pretty(&map).unwrap_or_default()
+    }
+
+    // A page showing the collage with each tile linking to its album or artist on Spotify
+    pub fn image_map_html(&self, image_file: &str, width: u32, height: u32) -> String {
+        let mut areas = String::new();
+        for region in &self.tiles.regions {
+            let Some((name, uri)) = self.artworks.get(region.image) else {
+                continue;
+            };
+            let Some(url) = open_url(uri) else {
+                continue;
+            };
+            let (x, y, size) = (region.x, region.y, region.size as i64);
+            let (center_x, center_y, radius) = (x + size / 2, y + size / 2, size / 2);
+            let (shape, coords) = match self.tiles.shape {
+                TileShape::Square => ("rect", format!("{},{},{},{}", x, y, x + size, y + size)),
+                TileShape::Circle => ("circle", format!("{},{},{}", center_x, center_y, radius)),
+                TileShape::Hexagon => {
+                    // Pointy-top, as tall as the tile
+                    let half_width = (radius as f32 * 3f32.sqrt() / 2.0).round() as i64;
+                    let corners = [
+                        (center_x, y),
+                        (center_x + half_width, center_y - radius / 2),
+                        (center_x + half_width, center_y + radius / 2),
+                        (center_x, y + size),
+                        (center_x - half_width, center_y + radius / 2),
+                        (center_x - half_width, center_y - radius / 2),
+                    ];
+                    ("poly", corners.iter().map(|(x, y)| format!("{},{}", x, y)).collect::<Vec<_>>().join(","))
+                }
+            };
+            let name = escape_html(name);
+            areas.push_str(&format!(
+                "    <area shape=\"{}\" coords=\"{}\" href=\"{}\" title=\"{}\" alt=\"{}\">\n",
+                shape, coords, url, name, name
+            ));
+        }
+        let title = escape_html(&format!("Spotify collage of {}", self.source));
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n  <meta charset=\"utf-8\">\n  <title>{}</title>\n</head>\n<body>\n  <img src=\"{}\" width=\"{}\" height=\"{}\" usemap=\"#collage\" alt=\"{}\">\n  <map name=\"collage\">\n{}  </map>\n</body>\n</html>\n",
+            title, escape_html(image_file), width, height, title, areas
+        )
+    }
+}
+
+// Encodes the collage's PNG in `format`, with the source tracks in its Exif description. Exif text
+// is ASCII, so PNGs also get the listing as it's written in a UTF-8 text chunk
+pub fn encode_collage(png: &[u8], format: ExportFormat, jpeg_quality: u8, details: Option<&CollageDetails>) -> Result<Vec<u8>, String> {
+    let image = image::load_from_memory(png).map_err(|e| e.to_string())?;
+    let exif = details.map(|details| exif_metadata(&to_ascii(&details.description(MAX_DESCRIPTION_BYTES)))).unwrap_or_default();
+    let mut bytes = Vec::new();
+    match format {
+        ExportFormat::Png => {
+            write_with_exif(&image, PngEncoder::new(&mut bytes), exif)?;
+            if let Some(details) = details {
+                bytes = with_png_text(bytes, PNG_TEXT_KEYWORD, &details.description(usize::MAX));
+            }
+        }
+        // JPEG has no transparency, so see-through parts turn black
+        ExportFormat::Jpeg => write_with_exif(&DynamicImage::ImageRgb8(image.to_rgb8()), JpegEncoder::new_with_quality(&mut bytes, jpeg_quality), exif)?,
+        ExportFormat::WebP => write_with_exif(&image, WebPEncoder::new_lossless(&mut bytes), exif)?,
+    }
+    Ok(bytes)
+}
+
+fn write_with_exif(image: &DynamicImage, mut encoder: impl ImageEncoder, exif: Vec<u8>) -> Result<(), String> {
+    if !exif.is_empty() {
+        encoder.set_exif_metadata(exif).map_err(|e| e.to_string())?;
+    }
+    image.write_with_encoder(encoder).map_err(|e| e.to_string())
+}
+
+// Accents are dropped and whatever else isn't ASCII becomes '?', so "Beyoncé" stays readable
+fn to_ascii(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c.is_ascii() {
+                return c;
+            }
+            let lower = c.to_lowercase().next().unwrap_or(c);
+            let folded = fold_diacritic(lower);
+            if !folded.is_ascii() {
+                '?'
+            } else if lower != c {
+                folded.to_ascii_uppercase()
+            } else {
+                folded
+            }
+        })
+        .collect()
+}
+
+// Adds an uncompressed iTXt chunk just before the PNG's closing IEND chunk
+fn with_png_text(mut png: Vec<u8>, keyword: &str, text: &str) -> Vec<u8> {
+    let Some(iend) = png.windows(4).rposition(|window| window == b"IEND").and_then(|position| position.checked_sub(4)) else {
+        return png;
+    };
+    // Keyword, no compression, and empty language and translated keyword
+    let mut chunk = b"iTXt".to_vec();
+    chunk.extend_from_slice(keyword.as_bytes());
+    chunk.extend_from_slice(&[0, 0, 0, 0, 0]);
+    chunk.extend_from_slice(text.as_bytes());
+
+    let mut bytes = ((chunk.len() - 4) as u32).to_be_bytes().to_vec();
+    bytes.extend_from_slice(&chunk);
+    bytes.extend_from_slice(&crc32(&chunk).to_be_bytes());
+    png.splice(iend..iend, bytes);
+    png
+}
+
+// The CRC PNG chunks end with, over the chunk type and data
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+// A little-endian TIFF block holding the ImageDescription and Software tags, which is what the
+// encoders embed as Exif. Both are ASCII
+fn exif_metadata(description: &str) -> Vec<u8> {
+    let entries = [(0x010E_u16, description), (0x0131, SOFTWARE)];
+    let data_start = 8 + 2 + entries.len() * 12 + 4;
+    let mut ifd = Vec::new();
+    let mut data = Vec::new();
+    ifd.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    for (tag, text) in entries {
+        let mut value = text.as_bytes().to_vec();
+        value.push(0);
+        ifd.extend_from_slice(&tag.to_le_bytes());
+        ifd.extend_from_slice(&2u16.to_le_bytes()); // ASCII
+        ifd.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        if value.len() <= 4 {
+            value.resize(4, 0);
+            ifd.extend_from_slice(&value);
+        } else {
+            ifd.extend_from_slice(&((data_start + data.len()) as u32).to_le_bytes());
+            data.extend_from_slice(&value);
+        }
+    }
+    ifd.extend_from_slice(&0u32.to_le_bytes()); // No further IFDs
+
+    let mut exif = b"II*\0".to_vec();
+    exif.extend_from_slice(&8u32.to_le_bytes());
+    exif.extend_from_slice(&ifd);
+    exif.extend_from_slice(&data);
+    exif
+}
+
+// Album and artist URIs; artwork known only by its image URL has none
+fn spotify_uri(key: &str) -> Option<&str> {
+    key.starts_with("spotify:").then_some(key)
+}
+
+// "spotify:album:ID" as "https://open.spotify.com/album/ID"
+fn open_url(key: &str) -> Option<String> {
+    let (kind, id) = spotify_uri(key)?.strip_prefix("spotify:")?.split_once(':')?;
+    Some(format!("https://open.spotify.com/{}/{}", kind, id))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn details() -> CollageDetails {
+        CollageDetails {
+            source: "Liked Songs".to_string(),
+            tracks: vec!["Beyoncé - Halo".to_string(), "宇多田ヒカル - First Love".to_string()],
+            artworks: Vec::new(),
+            tiles: TileMap { shape: TileShape::Square, regions: Vec::new() },
+        }
+    }
+
+    #[test]
+    fn exif_text_is_ascii() {
+        assert_eq!(to_ascii("Beyoncé - Ægir Øst"), "Beyonce - ?gir Ost");
+        assert_eq!(to_ascii("宇多田"), "???");
+        let exif = exif_metadata(&to_ascii(&details().description(MAX_DESCRIPTION_BYTES)));
+        assert!(exif.is_ascii());
+    }
+
+    #[test]
+    fn png_keeps_the_listing_in_utf8() {
+        let mut png = Vec::new();
+        DynamicImage::new_rgba8(2, 2).write_with_encoder(PngEncoder::new(&mut png)).unwrap();
+        let bytes = encode_collage(&png, ExportFormat::Png, 90, Some(&details())).unwrap();
+
+        let text = "宇多田ヒカル - First Love".as_bytes();
+        assert!(bytes.windows(text.len()).any(|window| window == text));
+        assert!(bytes.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]));
+        // The decoder checks chunk CRCs
+        assert!(image::load_from_memory(&bytes).is_ok());
+    }
+}
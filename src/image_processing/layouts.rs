@@ -0,0 +1,386 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::f32::consts::{PI, TAU};
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use super::collage::{grid_dimensions, mosaic_dimensions, CollageSettings, MosaicSettings, Progress};
+use super::color_analysis::{rgb_to_lab, ImageColorStats};
+use super::color_sort::{dominant_hue, self_organizing_layout};
+use crate::ui::app_state::{ColorSort, GradientDirection, StartingCorner};
+use wasm_bindgen::JsValue;
+
+const FEATURED_SHARE: usize = 12; // One featured cover per this many images in mixed layouts
+const MAX_FEATURED: usize = 8;
+
+// Where one image goes: a square tile whose top-left corner is at (x, y)
+#[derive(Clone, Copy)]
+pub struct Placement {
+    pub x: i64,
+    pub y: i64,
+    pub size: u32,
+    pub tint: Option<[u8; 3]>, // Color the tile is blended towards
+}
+
+impl Placement {
+    fn at(x: i64, y: i64, size: u32) -> Self {
+        Self { x, y, size, tint: None }
+    }
+}
+
+// What's cut from each square tile
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TileShape {
+    Square,
+    Hexagon, // Pointy-top, as tall as the tile
+    Circle,
+}
+
+pub struct Layout {
+    pub width: u32,
+    pub height: u32,
+    pub tiles: Vec<(usize, Placement)>, // Image index and where it goes; images that don't fit are left out
+    pub shape: TileShape,
+}
+
+// Gives the images in `order` the slots in turn
+fn assign(order: &[usize], slots: impl IntoIterator<Item = Placement>) -> Vec<(usize, Placement)> {
+    order.iter().copied().zip(slots).collect()
+}
+
+fn compare(a: f32, b: f32) -> Ordering {
+    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+}
+
+// Square tiles filled along the gradient, or arranged by similarity for the self-organizing sort,
+// which can take a while and so checks `progress` for a cancel as it goes
+pub async fn grid_layout(stats: &[ImageColorStats], order: &[usize], settings: &CollageSettings, progress: &mut Progress<'_>) -> Result<Layout, JsValue> {
+    let (cols, rows, tile_size) = grid_dimensions(stats.len(), settings.width, settings.height);
+    let cell = |(col, row): (u32, u32)| Placement::at((col * tile_size) as i64, (row * tile_size) as i64, tile_size);
+    let tiles = if settings.color_sort == ColorSort::SelfOrganizing {
+        self_organizing_layout(stats, cols, rows, progress).await?.into_iter().map(cell).enumerate().collect()
+    } else {
+        let positions = gradient_positions(cols, rows, stats.len(), settings.gradient_direction, settings.starting_corner);
+        assign(order, positions.into_iter().map(cell))
+    };
+    Ok(Layout { width: tile_size * cols, height: tile_size * rows, tiles, shape: TileShape::Square })
+}
+
+// Square tiles in rings around the middle, each ring walked clockwise from the top, so the
+// order spirals outwards
+pub fn spiral_layout(order: &[usize], width: u32, height: u32) -> Layout {
+    let (cols, rows, tile_size) = grid_dimensions(order.len(), width, height);
+    let (center_col, center_row) = ((cols as f32 - 1.0) / 2.0, (rows as f32 - 1.0) / 2.0);
+    let ring_and_angle = |&(col, row): &(u32, u32)| {
+        let (dx, dy) = (col as f32 - center_col, row as f32 - center_row);
+        let ring = dx.abs().max(dy.abs()).round();
+        let angle = (dx.atan2(-dy) + TAU) % TAU;
+        (ring, angle)
+    };
+    let mut cells: Vec<(u32, u32)> = (0..rows).flat_map(|row| (0..cols).map(move |col| (col, row))).collect();
+    cells.sort_by(|a, b| {
+        let (ring_a, angle_a) = ring_and_angle(a);
+        let (ring_b, angle_b) = ring_and_angle(b);
+        compare(ring_a, ring_b).then(compare(angle_a, angle_b))
+    });
+
+    let slots = cells.into_iter().map(|(col, row)| Placement::at((col * tile_size) as i64, (row * tile_size) as i64, tile_size));
+    Layout { width: tile_size * cols, height: tile_size * rows, tiles: assign(order, slots), shape: TileShape::Square }
+}
+
+// A grid of small tiles with a few covers featured at twice the size. The covers used by the most
+// tracks are featured, spread over the grid
+pub fn mixed_layout(order: &[usize], urls: &[String], track_counts: &[usize], width: u32, height: u32) -> Layout {
+    let image_count = order.len();
+
+    // Repeated tiles of one cover only feature it once
+    let mut by_tracks: Vec<usize> = (0..image_count).collect();
+    by_tracks.sort_by(|&a, &b| track_counts[b].cmp(&track_counts[a]));
+    let mut seen_urls = HashSet::new();
+    let featured_target = if image_count >= FEATURED_SHARE / 2 { (image_count / FEATURED_SHARE).clamp(1, MAX_FEATURED) } else { 0 };
+    let featured: HashSet<usize> = by_tracks
+        .into_iter()
+        .filter(|&image| seen_urls.insert(urls[image].as_str()))
+        .take(featured_target)
+        .collect();
+
+    // Featured covers take four cells each
+    let (cols, rows, tile_size) = grid_dimensions(image_count + featured.len() * 3, width, height);
+    let mut taken = vec![false; (cols * rows) as usize];
+    let mut big_cells = Vec::new();
+    if cols >= 2 && rows >= 2 {
+        for k in 0..featured.len() {
+            // Rows are evenly spaced and columns scattered by the golden ratio, so features don't line up
+            let row = ((k as f32 + 0.5) / featured.len() as f32 * (rows - 1) as f32) as u32;
+            let col = ((k as f32 * 0.618 + 0.3).fract() * (cols - 1) as f32) as u32;
+            let start = (row.min(rows - 2) * cols + col.min(cols - 2)) as usize;
+            let free = (start..taken.len()).chain(0..start).map(|cell| (cell as u32 % cols, cell as u32 / cols)).find(|&(col, row)| {
+                col + 1 < cols && row + 1 < rows && [(0, 0), (1, 0), (0, 1), (1, 1)].iter().all(|(dx, dy)| !taken[((row + dy) * cols + col + dx) as usize])
+            });
+            if let Some((col, row)) = free {
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    taken[((row + dy) * cols + col + dx) as usize] = true;
+                }
+                big_cells.push((col, row));
+            }
+        }
+    }
+    let mut small_cells: Vec<(u32, u32)> = (0..rows)
+        .flat_map(|row| (0..cols).map(move |col| (col, row)))
+        .filter(|&(col, row)| !taken[(row * cols + col) as usize])
+        .collect();
+
+    // Both kinds of tiles follow the color order diagonally from the top left
+    let diagonal = |&(col, row): &(u32, u32)| (col + row, row);
+    big_cells.sort_by_key(diagonal);
+    small_cells.sort_by_key(diagonal);
+    // Featured covers that found no room become small tiles
+    let big_order: Vec<usize> = order.iter().copied().filter(|image| featured.contains(image)).take(big_cells.len()).collect();
+    let small_order: Vec<usize> = order.iter().copied().filter(|image| !big_order.contains(image)).collect();
+
+    let mut tiles = assign(&big_order, big_cells.into_iter().map(|(col, row)| Placement::at((col * tile_size) as i64, (row * tile_size) as i64, tile_size * 2)));
+    tiles.extend(assign(&small_order, small_cells.into_iter().map(|(col, row)| Placement::at((col * tile_size) as i64, (row * tile_size) as i64, tile_size))));
+    Layout { width: tile_size * cols, height: tile_size * rows, tiles, shape: TileShape::Square }
+}
+
+// Pointy-top hexagons in offset rows, as large as still fits every image, filled diagonally
+pub fn hexagonal_layout(order: &[usize], width: u32, height: u32) -> Layout {
+    let image_count = order.len().max(1);
+    let sqrt3 = 3f32.sqrt();
+    let (width_f, height_f) = (width as f32, height as f32);
+
+    // The fewest columns, and so the largest hexagons, that fit every image
+    let (cols, rows, radius) = (1..)
+        .find_map(|cols: u32| {
+            let radius = width_f / (cols as f32 + 0.5) / sqrt3;
+            if height_f < radius * 2.0 {
+                return None;
+            }
+            let rows = ((height_f - radius * 2.0) / (radius * 1.5)) as u32 + 1;
+            ((cols * rows) as usize >= image_count).then_some((cols, rows, radius))
+        })
+        .unwrap_or_default();
+
+    let hex_width = radius * sqrt3;
+    let size = (radius * 2.0).round() as u32;
+    let mut cells: Vec<(u32, u32)> = (0..rows).flat_map(|row| (0..cols).map(move |col| (col, row))).collect();
+    cells.sort_by_key(|&(col, row)| (col + row, row));
+    let slots = cells.into_iter().map(|(col, row)| {
+        let offset = if row % 2 == 1 { hex_width / 2.0 } else { 0.0 };
+        // The square tile is wider than the hexagon cut from it
+        Placement::at(
+            (col as f32 * hex_width + offset - (radius * 2.0 - hex_width) / 2.0).round() as i64,
+            (row as f32 * radius * 1.5).round() as i64,
+            size,
+        )
+    });
+    Layout { width, height, tiles: assign(order, slots), shape: TileShape::Hexagon }
+}
+
+// Round tiles in rings around the center, with the grayest covers in the middle and the most
+// colorful outside, each ring going round the hues from the top. The starting hue turns the wheel
+pub fn color_wheel_layout(stats: &[ImageColorStats], hue_shift: f32, width: u32, height: u32) -> Layout {
+    let image_count = stats.len();
+    // Ring k (of radius k tiles) fits about 2πk round tiles without overlaps; the middle takes one
+    let ring_capacity = |ring: usize| if ring == 0 { 1 } else { (TAU * ring as f32).floor() as usize };
+    let mut ring_count = 1;
+    while (0..ring_count).map(ring_capacity).sum::<usize>() < image_count {
+        ring_count += 1;
+    }
+    let tile_size = (width.min(height) as f32 / (ring_count as f32 * 2.0 - 1.0)).floor().max(1.0);
+    let (center_x, center_y) = (width as f32 / 2.0, height as f32 / 2.0);
+
+    let colorfulness = |s: &ImageColorStats| s.average_lab[1].hypot(s.average_lab[2]);
+    let hue = |s: &ImageColorStats| (dominant_hue(s).unwrap_or(s.dominant_hue) + hue_shift) % 360.0;
+    let mut by_colorfulness: Vec<usize> = (0..image_count).collect();
+    by_colorfulness.sort_by(|&a, &b| compare(colorfulness(&stats[a]), colorfulness(&stats[b])));
+
+    let mut tiles = Vec::with_capacity(image_count);
+    let mut remaining = by_colorfulness.as_slice();
+    for ring in 0..ring_count {
+        let (ring_images, rest) = remaining.split_at(ring_capacity(ring).min(remaining.len()));
+        remaining = rest;
+        let mut ring_images = ring_images.to_vec();
+        ring_images.sort_by(|&a, &b| compare(hue(&stats[a]), hue(&stats[b])));
+
+        let radius = ring as f32 * tile_size;
+        for (slot, &image) in ring_images.iter().enumerate() {
+            let angle = slot as f32 / ring_images.len() as f32 * TAU - PI / 2.0;
+            tiles.push((image, Placement::at(
+                (center_x + radius * angle.cos() - tile_size / 2.0).round() as i64,
+                (center_y + radius * angle.sin() - tile_size / 2.0).round() as i64,
+                tile_size as u32,
+            )));
+        }
+    }
+    Layout { width, height, tiles, shape: TileShape::Circle }
+}
+
+// Recreates `target` from the covers: it's cut into cells, and each cell gets the cover whose
+// average color is closest. Covers are used at most `reuse_limit` times (0 for no limit) until
+// every cover is used up, after which the best match is used again so no cell stays empty. The
+// mosaic keeps the target's proportions and fits inside width × height
+pub fn photomosaic_layout(stats: &[ImageColorStats], urls: &[String], target: &DynamicImage, mosaic: &MosaicSettings, width: u32, height: u32) -> Layout {
+    let (cols, rows, tile_size) = mosaic_dimensions(target.width(), target.height(), mosaic.columns, width, height);
+    let cells = target.resize_exact(cols, rows, image::imageops::FilterType::Triangle).to_rgb8();
+
+    // Repeated tiles of one cover count as one candidate
+    let mut seen_urls = HashSet::new();
+    let candidates: Vec<usize> = (0..stats.len()).filter(|&image| seen_urls.insert(urls[image].as_str())).collect();
+
+    // Cells are visited in a scattered order, so the covers that run out aren't always missed in the same part of the picture
+    let cell_count = (cols * rows) as usize;
+    let mut stride = ((cell_count as f32 * 0.618) as usize).max(1);
+    while gcd(stride, cell_count) != 1 {
+        stride += 1;
+    }
+
+    let mut uses = vec![0; stats.len()];
+    let mut tiles = Vec::with_capacity(cell_count);
+    for step in 0..cell_count {
+        let cell = step * stride % cell_count;
+        let (col, row) = (cell as u32 % cols, cell as u32 / cols);
+        let color = cells.get_pixel(col, row).0;
+        let [l, a, b] = rgb_to_lab(color[0], color[1], color[2]);
+        let distance = |&&image: &&usize| {
+            let [l2, a2, b2] = stats[image].average_lab;
+            (l - l2).powi(2) + (a - a2).powi(2) + (b - b2).powi(2)
+        };
+        let best = candidates
+            .iter()
+            .filter(|&&image| mosaic.reuse_limit == 0 || uses[image] < mosaic.reuse_limit)
+            .min_by(|x, y| compare(distance(x), distance(y)))
+            .or_else(|| candidates.iter().min_by(|x, y| compare(distance(x), distance(y))));
+        if let Some(&image) = best {
+            uses[image] += 1;
+            tiles.push((image, Placement {
+                x: (col * tile_size) as i64,
+                y: (row * tile_size) as i64,
+                size: tile_size,
+                tint: (mosaic.tint > 0.0).then_some(color),
+            }));
+        }
+    }
+    Layout { width: tile_size * cols, height: tile_size * rows, tiles, shape: TileShape::Square }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+// Clears what's outside the shape inscribed in a square tile. Square tiles get their corners
+// rounded by `corner_radius`, a share of the tile size
+pub fn cut_tile(tile: &mut image::RgbaImage, shape: TileShape, corner_radius: f32) {
+    let radius = tile.height() as f32 / 2.0;
+    let corner = corner_radius.clamp(0.0, 0.5) * tile.height() as f32;
+    let half_width = radius * 3f32.sqrt() / 2.0;
+    let (center_x, center_y) = (tile.width() as f32 / 2.0, radius);
+    for (x, y, pixel) in tile.enumerate_pixels_mut() {
+        let dx = (x as f32 + 0.5 - center_x).abs();
+        let dy = (y as f32 + 0.5 - center_y).abs();
+        let outside = match shape {
+            TileShape::Square => {
+                // Outside the circle at the corner, past where the straight edges end
+                let (corner_dx, corner_dy) = (dx - (radius - corner), dy - (radius - corner));
+                corner_dx > 0.0 && corner_dy > 0.0 && corner_dx.hypot(corner_dy) > corner
+            }
+            TileShape::Hexagon => dx > half_width || dy > radius - dx / 3f32.sqrt(),
+            TileShape::Circle => dx.hypot(dy) > radius,
+        };
+        if outside {
+            pixel.0[3] = 0;
+        }
+    }
+}
+
+// Grid cells in the order the gradient fills them
+fn gradient_positions(best_cols: u32, best_rows: u32, image_count: usize, gradient_direction: GradientDirection, starting_corner: StartingCorner) -> Vec<(u32, u32)> {
+    // Generate positions based on gradient direction and starting corner
+    let mut positions = Vec::new();
+    match gradient_direction {
+        GradientDirection::Diagonal => {
+            for s in 0..(best_rows + best_cols - 1) {
+                for row in 0..best_rows {
+                    if s >= row {
+                        let col = s - row;
+                        if col < best_cols {
+                            positions.push((col, row));
+                        }
+                    }
+                }
+            }
+        },
+        GradientDirection::Vertical => { // Swapped with Horizontal
+            for row in 0..best_rows {
+                for col in 0..best_cols {
+                    positions.push((col, row));
+                }
+            }
+        },
+        GradientDirection::Horizontal => { // Swapped with Vertical
+            for col in 0..best_cols {
+                for row in 0..best_rows {
+                    positions.push((col, row));
+                }
+            }
+        },
+    }
+
+    // Adjust positions based on starting corner or side
+    match gradient_direction {
+        GradientDirection::Diagonal => match starting_corner {
+            StartingCorner::TopLeft => {},
+            StartingCorner::TopRight => {
+                for pos in &mut positions {
+                    pos.0 = best_cols - 1 - pos.0;
+                }
+            },
+            StartingCorner::BottomLeft => {
+                for pos in &mut positions {
+                    pos.1 = best_rows - 1 - pos.1;
+                }
+            },
+            StartingCorner::BottomRight => {
+                for pos in &mut positions {
+                    pos.0 = best_cols - 1 - pos.0;
+                    pos.1 = best_rows - 1 - pos.1;
+                }
+            },
+        },
+        GradientDirection::Vertical => match starting_corner { // Swapped with Horizontal
+            StartingCorner::TopLeft => {}, // Left
+            StartingCorner::TopRight => { // Right
+                for pos in &mut positions {
+                    pos.0 = best_cols - 1 - pos.0;
+                }
+            },
+            _ => {},
+        },
+        GradientDirection::Horizontal => match starting_corner { // Swapped with Vertical
+            StartingCorner::TopLeft => {}, // Top
+            StartingCorner::BottomLeft => { // Bottom
+                for pos in &mut positions {
+                    pos.1 = best_rows - 1 - pos.1;
+                }
+            },
+            _ => {},
+        },
+    }
+
+    // If there are more images than positions, expand the grid
+    let current_rows = best_rows;
+    let mut current_cols = best_cols;
+    while positions.len() < image_count {
+        current_cols += 1;
+        for row in 0..current_rows {
+            let col = current_cols - 1 - row;
+            if col < current_cols {
+                positions.push((col, row));
+            }
+        }
+        if positions.len() >= image_count {
+            break;
+        }
+    }
+
+    positions
+}
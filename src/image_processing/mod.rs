@@ -1,2 +1,8 @@
 pub mod color_analysis;
+pub mod color_sort;
 pub mod collage;
+pub mod cover;
+pub mod export;
+pub mod layouts;
+pub mod styling;
+pub mod worker;
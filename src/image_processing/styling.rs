@@ -0,0 +1,139 @@
+use ab_glyph::{Font, FontRef, PxScale, PxScaleFont, ScaleFont};
+use image::{imageops, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+const CAPTION_FONT: &str = "Ubuntu-Light"; // One of egui's built-in fonts, with the CJK font for what it lacks
+
+// What's behind the tiles, showing through gaps, padding and cut-out shapes
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CollageBackground {
+    Transparent,
+    Color,
+    BlurredArt, // The collage itself, blurred and darkened
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CaptionPosition {
+    Top,
+    Bottom,
+}
+
+// How the tiles are framed, independent of how they're arranged
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct CollageStyle {
+    pub gap: u32, // Pixels between tiles and around the edge
+    pub corner_radius: f32, // Share of the tile size, 0.5 making square tiles round
+    pub shadow: bool,
+    pub background: CollageBackground,
+    pub background_color: [u8; 3],
+    pub fill_size: bool, // Pads the collage out to exactly the requested size, centered
+    pub caption: Vec<String>, // Lines drawn over the collage, filled in when a collage starts
+    pub caption_position: CaptionPosition,
+}
+
+impl Default for CollageStyle {
+    fn default() -> Self {
+        Self {
+            gap: 0,
+            corner_radius: 0.0,
+            shadow: false,
+            background: CollageBackground::Transparent,
+            background_color: [18, 18, 18],
+            fill_size: false,
+            caption: Vec::new(),
+            caption_position: CaptionPosition::Bottom,
+        }
+    }
+}
+
+// A canvas filled with the chosen background. `tiles` is only needed for the blurred artwork
+pub fn background(style: &CollageStyle, tiles: &RgbaImage) -> RgbaImage {
+    let (width, height) = tiles.dimensions();
+    match style.background {
+        CollageBackground::Transparent => RgbaImage::new(width, height),
+        CollageBackground::Color => {
+            let [r, g, b] = style.background_color;
+            RgbaImage::from_pixel(width, height, Rgba([r, g, b, 255]))
+        }
+        CollageBackground::BlurredArt => {
+            // Blurring a small copy is much faster, and scaling it back up smooths it further
+            let small = imageops::resize(tiles, (width / 16).max(1), (height / 16).max(1), imageops::FilterType::Triangle);
+            let mut small = imageops::fast_blur(&small, 3.0);
+            for pixel in small.pixels_mut() {
+                let [r, g, b, a] = pixel.0;
+                let darken = |channel: u8| (channel as f32 * a as f32 / 255.0 * 0.6) as u8;
+                *pixel = Rgba([darken(r), darken(g), darken(b), 255]);
+            }
+            imageops::resize(&small, width, height, imageops::FilterType::Triangle)
+        }
+    }
+}
+
+// Draws a soft shadow below and right of everything opaque in `tiles`
+pub fn draw_shadow(canvas: &mut RgbaImage, tiles: &RgbaImage, tile_size: u32) {
+    let blur = (tile_size as f32 / 24.0).clamp(2.0, 24.0);
+    let mut shadow = RgbaImage::new(tiles.width(), tiles.height());
+    for (shadow_pixel, tile_pixel) in shadow.pixels_mut().zip(tiles.pixels()) {
+        shadow_pixel.0[3] = (tile_pixel.0[3] as f32 * 0.6) as u8;
+    }
+    let shadow = imageops::fast_blur(&shadow, blur);
+    let offset = (blur / 2.0).round() as i64;
+    imageops::overlay(canvas, &shadow, offset, offset);
+}
+
+// Draws the caption's lines centered on a dark band across the top or bottom. Characters Ubuntu
+// doesn't have, like playlist names in Japanese, come from the CJK font
+pub fn draw_caption(canvas: &mut RgbaImage, lines: &[String], position: CaptionPosition) {
+    let fonts = egui::FontDefinitions::default();
+    let latin = fonts.font_data.get(CAPTION_FONT).and_then(|data| FontRef::try_from_slice(&data.font).ok());
+    let cjk = FontRef::try_from_slice(crate::CJK_FONT).ok();
+    let (width, height) = canvas.dimensions();
+    let font_size = (width.min(height) as f32 / 18.0).clamp(14.0, 96.0);
+    let scaled: Vec<PxScaleFont<&FontRef>> = latin.iter().chain(&cjk).map(|font| font.as_scaled(PxScale::from(font_size))).collect();
+    let Some(primary) = scaled.first() else {
+        return;
+    };
+    let font_for = |c: char| scaled.iter().find(|font| font.glyph_id(c).0 != 0).unwrap_or(primary);
+    let line_height = scaled.iter().map(|font| font.height() + font.line_gap()).fold(0.0, f32::max);
+    let ascent = scaled.iter().map(|font| font.ascent()).fold(0.0, f32::max);
+    let padding = font_size / 2.0;
+
+    let band_height = (line_height * lines.len() as f32 + padding * 2.0).min(height as f32);
+    let band_top = match position {
+        CaptionPosition::Top => 0.0,
+        CaptionPosition::Bottom => height as f32 - band_height,
+    };
+    let band = RgbaImage::from_pixel(width, band_height as u32, Rgba([0, 0, 0, 140]));
+    imageops::overlay(canvas, &band, 0, band_top as i64);
+
+    for (line_index, line) in lines.iter().enumerate() {
+        let line_width: f32 = line.chars().map(|c| {
+            let font = font_for(c);
+            font.h_advance(font.glyph_id(c))
+        }).sum();
+        let mut x = (width as f32 - line_width) / 2.0;
+        let baseline = band_top + padding + ascent + line_index as f32 * line_height;
+        for c in line.chars() {
+            let font = font_for(c);
+            let glyph = font.scaled_glyph(c);
+            let advance = font.h_advance(glyph.id);
+            let glyph = glyph.id.with_scale_and_position(font_size, ab_glyph::point(x, baseline));
+            x += advance;
+            let Some(outline) = font.outline_glyph(glyph) else {
+                continue;
+            };
+            let bounds = outline.px_bounds();
+            outline.draw(|gx, gy, coverage| {
+                let (px, py) = (bounds.min.x as i64 + gx as i64, bounds.min.y as i64 + gy as i64);
+                if px < 0 || py < 0 || px >= width as i64 || py >= height as i64 {
+                    return;
+                }
+                let pixel = canvas.get_pixel_mut(px as u32, py as u32);
+                for channel in &mut pixel.0[..3] {
+                    *channel = (*channel as f32 + (255.0 - *channel as f32) * coverage) as u8;
+                }
+                pixel.0[3] = pixel.0[3].max((coverage * 255.0) as u8);
+            });
+        }
+    }
+}
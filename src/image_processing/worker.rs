@@ -0,0 +1,307 @@
+use std::cell::RefCell;
+use std::io::Cursor;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use futures::channel::mpsc;
+use futures::StreamExt;
+use image::{DynamicImage, RgbaImage};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{DedicatedWorkerGlobalScope, MessageEvent, Worker, WorkerOptions, WorkerType};
+use super::collage::{create_collage, CollageImage, CollageSettings, TileMap};
+use crate::utils::log_error;
+
+// Collages are analysed and drawn in a worker running its own copy of this module, so the UI
+// keeps running meanwhile. Messages between the two:
+//   page → worker  { type: "compose", id, job, images: [{ width, height, pixels }], target? }
+//                  { type: "cancel", id }
+//   worker → page  { type: "progress", id, done, total }
+//                  { type: "done", id, png, tiles }
+//                  { type: "error", id, message }
+const WORKER_SCRIPT: &str = "./collage_worker.js";
+
+// A collage to render: its settings, and how the images are used
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CollageJob {
+    pub settings: CollageSettings,
+    pub tiles: Vec<usize>, // Tiles each image fills, in the order of the images
+    pub urls: Vec<String>, // Where each image came from, which keys its cached color stats
+    pub track_counts: Vec<usize>, // Tracks using each image
+    #[serde(skip)]
+    pub mosaic_target: Option<Vec<u8>>, // Encoded picture for the photomosaic, sent as its own buffer
+}
+
+enum WorkerEvent {
+    Progress(usize, usize),
+    Done(Vec<u8>, TileMap),
+    Failed(String),
+}
+
+type WorkerHandler = Closure<dyn FnMut(JsValue)>;
+
+thread_local! {
+    // Started on the first collage and kept for the next ones, along with the handlers of the
+    // collage it's working on
+    static WORKER: RefCell<Option<Worker>> = const { RefCell::new(None) };
+    static WORKER_HANDLERS: RefCell<Vec<WorkerHandler>> = const { RefCell::new(Vec::new()) };
+}
+
+static NEXT_JOB_ID: AtomicU32 = AtomicU32::new(1);
+
+// Renders the collage as a PNG in the worker, or on the page when workers aren't available, along
+// with where its tiles went. Returns None once `is_cancelled` says so, or if the collage couldn't be made
+pub async fn compose_collage(
+    job: CollageJob,
+    images: Vec<Arc<DynamicImage>>,
+    is_cancelled: impl Fn() -> bool,
+    mut on_progress: impl FnMut(usize, usize),
+) -> Option<(Vec<u8>, TileMap)> {
+    let Some(worker) = collage_worker() else {
+        return compose_on_page(&job, &images, &is_cancelled, &mut on_progress).await;
+    };
+
+    let id = NEXT_JOB_ID.fetch_add(1, Ordering::Relaxed);
+    let (sender, mut events) = mpsc::unbounded();
+    listen_to_worker(&worker, id, sender);
+
+    if let Err(e) = post_job(&worker, id, &job, &images) {
+        log_error(&format!("Failed to send collage to worker: {:?}", e));
+        return compose_on_page(&job, &images, &is_cancelled, &mut on_progress).await;
+    }
+
+    // A newer collage replaces these handlers, which ends this stream
+    while let Some(event) = events.next().await {
+        if is_cancelled() {
+            if let Err(e) = worker_message("cancel", id, &[]).and_then(|message| worker.post_message(&message)) {
+                log_error(&format!("Failed to cancel collage: {:?}", e));
+            }
+            return None;
+        }
+        match event {
+            WorkerEvent::Progress(done, total) => on_progress(done, total),
+            WorkerEvent::Done(png, tiles) => return Some((png, tiles)),
+            WorkerEvent::Failed(message) => {
+                // A worker that failed to load is started again next time
+                log_error(&format!("Collage worker failed: {}", message));
+                if let Some(worker) = WORKER.with(|cell| cell.borrow_mut().take()) {
+                    worker.terminate();
+                }
+                return compose_on_page(&job, &images, &is_cancelled, &mut on_progress).await;
+            }
+        }
+    }
+    None
+}
+
+fn collage_worker() -> Option<Worker> {
+    WORKER.with(|cell| {
+        let mut worker = cell.borrow_mut();
+        if worker.is_none() {
+            let options = WorkerOptions::new();
+            options.set_type(WorkerType::Module);
+            match Worker::new_with_options(WORKER_SCRIPT, &options) {
+                Ok(started) => *worker = Some(started),
+                Err(e) => log_error(&format!("Failed to start collage worker: {:?}", e)),
+            }
+        }
+        worker.clone()
+    })
+}
+
+// Routes the worker's messages about collage `id` into `sender`, dropping the previous collage's handlers
+fn listen_to_worker(worker: &Worker, id: u32, sender: mpsc::UnboundedSender<WorkerEvent>) {
+    let error_sender = sender.clone();
+    let on_message = WorkerHandler::new(move |event: JsValue| {
+        let Some(data) = event.dyn_ref::<MessageEvent>().map(|event| event.data()) else {
+            return;
+        };
+        if get_number(&data, "id") as u32 != id {
+            return;
+        }
+        let event = match get_string(&data, "type").as_str() {
+            "progress" => WorkerEvent::Progress(get_number(&data, "done") as usize, get_number(&data, "total") as usize),
+            "done" => match (js_sys::Reflect::get(&data, &"png".into()), serde_json::from_str(&get_string(&data, "tiles"))) {
+                (Ok(png), Ok(tiles)) => WorkerEvent::Done(js_sys::Uint8Array::new(&png).to_vec(), tiles),
+                _ => WorkerEvent::Failed("Collage arrived without an image".to_string()),
+            },
+            "error" => WorkerEvent::Failed(get_string(&data, "message")),
+            _ => return,
+        };
+        let _ = sender.unbounded_send(event);
+    });
+    // Errors loading the script or the module aren't tied to a collage, so they fail the current one
+    let on_error = WorkerHandler::new(move |event: JsValue| {
+        let message = event
+            .dyn_ref::<web_sys::ErrorEvent>()
+            .map(|event| event.message())
+            .unwrap_or_else(|| "The worker couldn't be loaded".to_string());
+        let _ = error_sender.unbounded_send(WorkerEvent::Failed(message));
+    });
+
+    worker.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    worker.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+    WORKER_HANDLERS.with(|handlers| *handlers.borrow_mut() = vec![on_message, on_error]);
+}
+
+// Sends the images as raw pixels, transferring their buffers rather than copying them
+fn post_job(worker: &Worker, id: u32, job: &CollageJob, images: &[Arc<DynamicImage>]) -> Result<(), JsValue> {
+    let job_json = serde_json::to_string(job).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let image_list = js_sys::Array::new();
+    let transfer = js_sys::Array::new();
+    for image in images {
+        let rgba = image.to_rgba8();
+        let pixels = js_sys::Uint8Array::from(rgba.as_raw().as_slice());
+        let entry = js_sys::Object::new();
+        js_sys::Reflect::set(&entry, &"width".into(), &rgba.width().into())?;
+        js_sys::Reflect::set(&entry, &"height".into(), &rgba.height().into())?;
+        js_sys::Reflect::set(&entry, &"pixels".into(), &pixels)?;
+        image_list.push(&entry);
+        transfer.push(&pixels.buffer());
+    }
+
+    let message = worker_message("compose", id, &[("job", job_json.into()), ("images", image_list.into())])?;
+    if let Some(target) = &job.mosaic_target {
+        let target = js_sys::Uint8Array::from(target.as_slice());
+        js_sys::Reflect::set(&message, &"target".into(), &target)?;
+        transfer.push(&target.buffer());
+    }
+    worker.post_message_with_transfer(&message, &transfer)
+}
+
+async fn compose_on_page(
+    job: &CollageJob,
+    images: &[Arc<DynamicImage>],
+    is_cancelled: &impl Fn() -> bool,
+    on_progress: &mut impl FnMut(usize, usize),
+) -> Option<(Vec<u8>, TileMap)> {
+    let images = images.iter().map(|image| (**image).clone()).collect();
+    let mut report = |done, total| {
+        on_progress(done, total);
+        !is_cancelled()
+    };
+    render_png(job, images, &mut report).await.ok()
+}
+
+// Shared by the worker and the page: lays the images out as the job describes and encodes the result
+async fn render_png(job: &CollageJob, images: Vec<DynamicImage>, on_progress: &mut dyn FnMut(usize, usize) -> bool) -> Result<(Vec<u8>, TileMap), JsValue> {
+    let mut tiles = Vec::new();
+    let mut tile_images = Vec::new(); // The job's image behind each tile
+    for (index, image) in images.into_iter().enumerate() {
+        for _ in 0..job.tiles[index] {
+            tile_images.push(index);
+            tiles.push(CollageImage {
+                url: job.urls[index].clone(),
+                image: image.clone(),
+                track_count: job.track_counts[index],
+            });
+        }
+    }
+    // The picture is decoded here rather than on the page, since photos can be large
+    let mosaic_target = match &job.mosaic_target {
+        Some(bytes) => Some(image::load_from_memory(bytes).map_err(|e| JsValue::from_str(&format!("Couldn't read the photomosaic picture: {}", e)))?),
+        None => None,
+    };
+    let (collage, mut tile_map) = create_collage(tiles, &job.settings, mosaic_target.as_ref(), on_progress).await?;
+    for region in &mut tile_map.regions {
+        region.image = tile_images[region.image];
+    }
+    let mut cursor = Cursor::new(Vec::new());
+    collage
+        .write_to(&mut cursor, image::ImageFormat::Png)
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    Ok((cursor.into_inner(), tile_map))
+}
+
+// The collage the worker is on. Cancelling or starting another one changes it, which stops the current one
+static ACTIVE_JOB: AtomicU32 = AtomicU32::new(0);
+
+// Called by collage_worker.js with each message the worker receives
+#[wasm_bindgen]
+pub async fn collage_worker_handle(message: JsValue) {
+    let id = get_number(&message, "id") as u32;
+    match get_string(&message, "type").as_str() {
+        "cancel" => {
+            let _ = ACTIVE_JOB.compare_exchange(id, 0, Ordering::Relaxed, Ordering::Relaxed);
+        }
+        "compose" => {
+            ACTIVE_JOB.store(id, Ordering::Relaxed);
+            let reply = match compose_in_worker(id, &message).await {
+                Ok((png, tiles)) => {
+                    let png = js_sys::Uint8Array::from(png.as_slice());
+                    let tiles = serde_json::to_string(&tiles).unwrap_or_default();
+                    worker_message("done", id, &[("png", png.clone().into()), ("tiles", tiles.into())])
+                        .and_then(|reply| post_from_worker(&reply, Some(&png.buffer())))
+                }
+                // A cancelled collage doesn't need an answer
+                Err(_) if ACTIVE_JOB.load(Ordering::Relaxed) != id => Ok(()),
+                Err(e) => worker_message("error", id, &[("message", JsValue::from_str(&format!("{:?}", e)))])
+                    .and_then(|reply| post_from_worker(&reply, None)),
+            };
+            if let Err(e) = reply {
+                log_error(&format!("Failed to reply from collage worker: {:?}", e));
+            }
+        }
+        _ => {}
+    }
+}
+
+async fn compose_in_worker(id: u32, message: &JsValue) -> Result<(Vec<u8>, TileMap), JsValue> {
+    let mut job: CollageJob = serde_json::from_str(&get_string(message, "job"))
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let target = js_sys::Reflect::get(message, &"target".into())?;
+    if !target.is_undefined() {
+        job.mosaic_target = Some(js_sys::Uint8Array::new(&target).to_vec());
+    }
+    let entries: js_sys::Array = js_sys::Reflect::get(message, &"images".into())?.dyn_into()?;
+    let images = entries
+        .iter()
+        .filter_map(|entry| {
+            let pixels = js_sys::Reflect::get(&entry, &"pixels".into()).ok()?;
+            let pixels = js_sys::Uint8Array::new(&pixels).to_vec();
+            RgbaImage::from_raw(get_number(&entry, "width") as u32, get_number(&entry, "height") as u32, pixels)
+                .map(DynamicImage::ImageRgba8)
+        })
+        .collect();
+
+    // Progress goes out once per percent so the page isn't flooded with messages
+    let mut last_percent = None;
+    let mut report = |done: usize, total: usize| {
+        let percent = done * 100 / total.max(1);
+        if last_percent != Some(percent) {
+            last_percent = Some(percent);
+            let progress = worker_message("progress", id, &[("done", done.into()), ("total", total.into())]);
+            if let Err(e) = progress.and_then(|message| post_from_worker(&message, None)) {
+                log_error(&format!("Failed to report collage progress: {:?}", e));
+            }
+        }
+        ACTIVE_JOB.load(Ordering::Relaxed) == id
+    };
+    render_png(&job, images, &mut report).await
+}
+
+fn worker_message(kind: &str, id: u32, fields: &[(&str, JsValue)]) -> Result<js_sys::Object, JsValue> {
+    let message = js_sys::Object::new();
+    js_sys::Reflect::set(&message, &"type".into(), &kind.into())?;
+    js_sys::Reflect::set(&message, &"id".into(), &id.into())?;
+    for (key, value) in fields {
+        js_sys::Reflect::set(&message, &(*key).into(), value)?;
+    }
+    Ok(message)
+}
+
+fn post_from_worker(message: &JsValue, transfer: Option<&JsValue>) -> Result<(), JsValue> {
+    let scope: DedicatedWorkerGlobalScope = js_sys::global().dyn_into()?;
+    match transfer {
+        Some(buffer) => scope.post_message_with_transfer(message, &js_sys::Array::of1(buffer)),
+        None => scope.post_message(message),
+    }
+}
+
+fn get_number(value: &JsValue, key: &str) -> f64 {
+    js_sys::Reflect::get(value, &key.into()).ok().and_then(|v| v.as_f64()).unwrap_or_default()
+}
+
+fn get_string(value: &JsValue, key: &str) -> String {
+    js_sys::Reflect::get(value, &key.into()).ok().and_then(|v| v.as_string()).unwrap_or_default()
+}
@@ -5,6 +5,9 @@ mod storage;
 mod mediaplayer;
 mod image_processing;
 
+// Covers Japanese, Chinese and Korean, for the UI and for collage captions
+const CJK_FONT: &[u8] = include_bytes!("../assets/NotoSansCJKjp-Regular.otf");
+
 #[wasm_bindgen]
 extern "C" {
     pub fn loginWithSpotify();
@@ -25,6 +28,7 @@ pub async fn check_active_playback() -> bool {
 pub async fn start() -> Result<(), JsValue> {
     console_error_panic_hook::set_once();
     panic::set_hook(Box::new(console_error_panic_hook::hook));
+    mediaplayer::media_session::init_media_session();
     
     let canvas_element = web_sys::window()
         .unwrap()
@@ -47,7 +51,7 @@ pub async fn start() -> Result<(), JsValue> {
                 // Add support for Asian characters
                 fonts.font_data.insert(
                     "noto_sans".to_owned(),
-                    egui::FontData::from_static(include_bytes!("../assets/NotoSansCJKjp-Regular.otf")).into()
+                    egui::FontData::from_static(CJK_FONT).into()
                 );
                 
                 // Add Noto Sans as primary font for all text styles
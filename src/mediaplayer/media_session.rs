@@ -0,0 +1,237 @@
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::spawn_local;
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+use crate::api_request::track_status::{pause_playback, resume_playback, seek_playback, skip_to_next, skip_to_previous};
+use crate::api_request::models::TrackInfo;
+use crate::api_request::token::get_token;
+
+// Key of the track last published to the Media Session, so metadata is only rebuilt on track change
+static LAST_TRACK_KEY: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+// The playing track, refreshed on each sync so the player doesn't read it back from JS every frame
+static CURRENT_TRACK: Lazy<Mutex<Option<TrackInfo>>> = Lazy::new(|| Mutex::new(None));
+
+struct SessionTrack {
+    title: String,
+    artist: String,
+    album: String,
+    artwork: Vec<String>,
+}
+
+fn media_session() -> Option<JsValue> {
+    let window = web_sys::window()?;
+    let navigator = js_sys::Reflect::get(&window, &"navigator".into()).ok()?;
+    let session = js_sys::Reflect::get(&navigator, &"mediaSession".into()).ok()?;
+    if session.is_undefined() || session.is_null() {
+        None
+    } else {
+        Some(session)
+    }
+}
+
+fn set_action_handler(session: &JsValue, action: &str, handler: Closure<dyn FnMut(JsValue)>) {
+    if let Ok(set_handler) = js_sys::Reflect::get(session, &"setActionHandler".into()) {
+        if let Some(func) = set_handler.dyn_ref::<js_sys::Function>() {
+            // Browsers throw for actions they don't support, which is fine to ignore
+            let _ = func.call2(session, &action.into(), handler.as_ref().unchecked_ref());
+        }
+    }
+    handler.forget();
+}
+
+// Registers the OS media key / lock screen handlers, routing them into the track_status API calls
+pub fn init_media_session() {
+    let session = match media_session() {
+        Some(session) => session,
+        None => {
+            web_sys::console::log_1(&"Media Session API not available".into());
+            return;
+        }
+    };
+
+    set_action_handler(&session, "play", Closure::new(|_: JsValue| {
+        spawn_local(async {
+            resume_playback().await;
+        });
+    }));
+
+    set_action_handler(&session, "pause", Closure::new(|_: JsValue| {
+        spawn_local(async {
+            pause_playback().await;
+        });
+    }));
+
+    set_action_handler(&session, "nexttrack", Closure::new(|_: JsValue| {
+        if let Some(token) = get_token() {
+            spawn_local(async move {
+                skip_to_next(token).await;
+            });
+        }
+    }));
+
+    set_action_handler(&session, "previoustrack", Closure::new(|_: JsValue| {
+        if let Some(token) = get_token() {
+            spawn_local(async move {
+                skip_to_previous(token).await;
+            });
+        }
+    }));
+
+    set_action_handler(&session, "seekto", Closure::new(|details: JsValue| {
+        // seekTime is given in seconds
+        let seek_time = js_sys::Reflect::get(&details, &"seekTime".into())
+            .ok()
+            .and_then(|value| value.as_f64());
+        if let Some(seconds) = seek_time {
+            let position_ms = (seconds * 1000.0) as i32;
+            spawn_local(async move {
+                seek_playback(position_ms).await;
+            });
+        }
+    }));
+}
+
+// Reads the current track out of window.currentPlayerState (set by the SDK or get_current_playback)
+fn current_track(window: &web_sys::Window) -> Option<SessionTrack> {
+    let state = js_sys::Reflect::get(window, &"currentPlayerState".into()).ok()?;
+    if !state.is_object() {
+        return None;
+    }
+    let track_window = js_sys::Reflect::get(&state, &"track_window".into()).ok()?;
+    let track = js_sys::Reflect::get(&track_window, &"current_track".into()).ok()?;
+    let title = js_sys::Reflect::get(&track, &"name".into()).ok()?.as_string()?;
+
+    let artists_value = js_sys::Reflect::get(&track, &"artists".into()).ok()?;
+    let artist = js_sys::Array::from(&artists_value)
+        .iter()
+        .filter_map(|artist| js_sys::Reflect::get(&artist, &"name".into()).ok()?.as_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let album_value = js_sys::Reflect::get(&track, &"album".into()).ok()?;
+    let album = js_sys::Reflect::get(&album_value, &"name".into())
+        .ok()
+        .and_then(|name| name.as_string())
+        .unwrap_or_default();
+    let artwork = js_sys::Reflect::get(&album_value, &"images".into())
+        .map(|images| {
+            js_sys::Array::from(&images)
+                .iter()
+                .filter_map(|image| js_sys::Reflect::get(&image, &"url".into()).ok()?.as_string())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(SessionTrack { title, artist, album, artwork })
+}
+
+fn build_metadata(window: &web_sys::Window, track: &SessionTrack) -> Option<JsValue> {
+    let constructor = js_sys::Reflect::get(window, &"MediaMetadata".into()).ok()?;
+    let constructor = constructor.dyn_ref::<js_sys::Function>()?;
+
+    let artwork = js_sys::Array::new();
+    for url in &track.artwork {
+        let image = js_sys::Object::new();
+        let _ = js_sys::Reflect::set(&image, &"src".into(), &url.into());
+        artwork.push(&image);
+    }
+
+    let init = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&init, &"title".into(), &track.title.as_str().into());
+    let _ = js_sys::Reflect::set(&init, &"artist".into(), &track.artist.as_str().into());
+    let _ = js_sys::Reflect::set(&init, &"album".into(), &track.album.as_str().into());
+    let _ = js_sys::Reflect::set(&init, &"artwork".into(), &artwork);
+
+    js_sys::Reflect::construct(constructor, &js_sys::Array::of1(&init)).ok()
+}
+
+// The track last seen by sync_media_session
+pub fn current_track_info() -> Option<TrackInfo> {
+    CURRENT_TRACK.lock().unwrap().clone()
+}
+
+// Caches the SDK's current track, which has the same shape as a Web API track object. It's only
+// converted when the URI changes, since syncs also come with every position update
+fn update_current_track(window: &web_sys::Window) {
+    let track = js_sys::Reflect::get(window, &"currentPlayerState".into())
+        .ok()
+        .filter(|state| state.is_object())
+        .and_then(|state| js_sys::Reflect::get(&state, &"track_window".into()).ok())
+        .and_then(|track_window| js_sys::Reflect::get(&track_window, &"current_track".into()).ok())
+        .filter(|track| track.is_object());
+    let uri = track
+        .as_ref()
+        .and_then(|track| js_sys::Reflect::get(track, &"uri".into()).ok()?.as_string());
+
+    let mut current = CURRENT_TRACK.lock().unwrap();
+    if current.as_ref().map(|current| &current.uri) == uri.as_ref() {
+        return;
+    }
+    *current = track
+        .and_then(|track| js_sys::JSON::stringify(&track).ok()?.as_string())
+        .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+        .and_then(|track| TrackInfo::from_json(&track, ""));
+}
+
+// Publishes the current track, playback state and position to navigator.mediaSession
+#[wasm_bindgen]
+pub fn sync_media_session() {
+    let window = web_sys::window().expect("no global window exists");
+    update_current_track(&window);
+    let session = match media_session() {
+        Some(session) => session,
+        None => return,
+    };
+
+    let track = current_track(&window);
+    let track_key = track.as_ref().map(|track| format!("{}\u{1f}{}", track.title, track.artist));
+    let mut last_key = LAST_TRACK_KEY.lock().unwrap();
+    if *last_key != track_key {
+        let metadata = track
+            .as_ref()
+            .and_then(|track| build_metadata(&window, track))
+            .unwrap_or(JsValue::NULL);
+        let _ = js_sys::Reflect::set(&session, &"metadata".into(), &metadata);
+        *last_key = track_key;
+    }
+    drop(last_key);
+
+    let is_playing = js_sys::Reflect::get(&window, &"isPlaying".into())
+        .ok()
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false);
+    let playback_state = if track.is_none() {
+        "none"
+    } else if is_playing {
+        "playing"
+    } else {
+        "paused"
+    };
+    let _ = js_sys::Reflect::set(&session, &"playbackState".into(), &playback_state.into());
+
+    // Position state is given in seconds; the browser extrapolates from playbackRate between updates
+    let duration_ms = js_sys::Reflect::get(&window, &"totalDuration".into())
+        .ok()
+        .and_then(|value| value.as_f64())
+        .unwrap_or(0.0);
+    let position_ms = js_sys::Reflect::get(&window, &"currentPlaybackTime".into())
+        .ok()
+        .and_then(|value| value.as_f64())
+        .unwrap_or(0.0);
+
+    if let Ok(set_position) = js_sys::Reflect::get(&session, &"setPositionState".into()) {
+        if let Some(func) = set_position.dyn_ref::<js_sys::Function>() {
+            if track.is_some() && duration_ms > 0.0 {
+                let position_state = js_sys::Object::new();
+                let _ = js_sys::Reflect::set(&position_state, &"duration".into(), &(duration_ms / 1000.0).into());
+                let _ = js_sys::Reflect::set(&position_state, &"position".into(), &(position_ms.clamp(0.0, duration_ms) / 1000.0).into());
+                let _ = js_sys::Reflect::set(&position_state, &"playbackRate".into(), &1.0.into());
+                let _ = func.call1(&session, &position_state);
+            } else {
+                // Calling with no argument clears the position state
+                let _ = func.call0(&session);
+            }
+        }
+    }
+}
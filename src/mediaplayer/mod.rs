@@ -1,2 +1,3 @@
 pub mod scrubber;
 pub mod mediaplayerwidget;
+pub mod media_session;
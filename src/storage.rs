@@ -1,13 +1,29 @@
 use web_sys::window;
-use crate::api_request::models::StoredTracks;
+use crate::api_request::models::{StoredPlaylist, StoredTracks, TrackInfo};
+use crate::ui::dock::DockTab;
+use crate::ui::TrackList;
+use crate::ui::layouts::LayoutPreset;
+use crate::ui::session::SessionSnapshot;
+use crate::ui::track_table::TableConfig;
+use std::collections::HashMap;
+use egui_dock::DockState;
 
 const TRACKS_KEY: &str = "spotify_tracks";
+const DOCK_LAYOUT_KEY: &str = "dock_layout";
+const LAYOUT_PRESETS_KEY: &str = "layout_presets";
+const SESSION_KEY: &str = "ui_session";
+const TABLE_CONFIGS_KEY: &str = "table_configs";
+const PLAYLIST_TRACKS_KEY_PREFIX: &str = "playlist_tracks_";
+const CACHED_PLAYLISTS_KEY: &str = "cached_playlists";
+const MAX_CACHED_PLAYLISTS: usize = 20; // localStorage's ~5 MB is shared with the liked songs cache
 const CACHE_DURATION: u64 = 24 * 60 * 60 * 1000; // 24 hours in milliseconds
+const TRACKS_FORMAT_VERSION: u32 = 2; // Bump when StoredTracks changes shape
 
-pub fn save_tracks(tracks: &Vec<(String, String, String, String)>, total: i32) -> Result<(), String> {
+pub fn save_tracks(tracks: &[TrackInfo], total: i32) -> Result<(), String> {
     if let Some(storage) = get_local_storage() {
         let stored_tracks = StoredTracks {
-            tracks: tracks.clone(),
+            version: TRACKS_FORMAT_VERSION,
+            tracks: tracks.to_vec(),
             total,
             timestamp: js_sys::Date::now() as u64,
         };
@@ -28,6 +44,16 @@ pub fn load_tracks() -> Option<StoredTracks> {
     let storage = get_local_storage()?;
     let json = storage.get_item(TRACKS_KEY).ok()??;
     
+    // Caches in another format lack fields the track views need, so the library is fetched again
+    #[derive(serde::Deserialize)]
+    struct Format {
+        #[serde(default)]
+        version: u32,
+    }
+    if serde_json::from_str::<Format>(&json).map_or(true, |format| format.version != TRACKS_FORMAT_VERSION) {
+        let _ = storage.remove_item(TRACKS_KEY);
+        return None;
+    }
     let stored_tracks: StoredTracks = serde_json::from_str(&json).ok()?;
     
     // Check if cache is still valid (within 24 hours)
@@ -41,6 +67,154 @@ pub fn load_tracks() -> Option<StoredTracks> {
     }
 }
 
+// Saves an already serialized dock tree; the caller serializes it to detect changes anyway
+pub fn save_dock_layout(layout_json: &str) -> Result<(), String> {
+    let storage = get_local_storage().ok_or_else(|| "LocalStorage not available".to_string())?;
+    storage.set_item(DOCK_LAYOUT_KEY, layout_json)
+        .map_err(|e| format!("Failed to save to localStorage: {:?}", e))
+}
+
+pub fn load_dock_layout() -> Option<DockState<DockTab>> {
+    let storage = get_local_storage()?;
+    let json = storage.get_item(DOCK_LAYOUT_KEY).ok()??;
+    serde_json::from_str(&json).ok()
+}
+
+pub fn save_layout_presets(presets: &[LayoutPreset]) -> Result<(), String> {
+    let storage = get_local_storage().ok_or_else(|| "LocalStorage not available".to_string())?;
+    let json = serde_json::to_string(presets)
+        .map_err(|e| format!("Failed to serialize layout presets: {}", e))?;
+    storage.set_item(LAYOUT_PRESETS_KEY, &json)
+        .map_err(|e| format!("Failed to save to localStorage: {:?}", e))
+}
+
+pub fn load_layout_presets() -> Vec<LayoutPreset> {
+    get_local_storage()
+        .and_then(|storage| storage.get_item(LAYOUT_PRESETS_KEY).ok().flatten())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+// Saves an already serialized session; the caller serializes it to detect changes anyway
+pub fn save_session(session_json: &str) -> Result<(), String> {
+    let storage = get_local_storage().ok_or_else(|| "LocalStorage not available".to_string())?;
+    storage.set_item(SESSION_KEY, session_json)
+        .map_err(|e| format!("Failed to save to localStorage: {:?}", e))
+}
+
+pub fn load_session() -> Option<SessionSnapshot> {
+    let storage = get_local_storage()?;
+    let json = storage.get_item(SESSION_KEY).ok()??;
+    serde_json::from_str(&json).ok()
+}
+
+pub fn save_table_configs(configs: &HashMap<String, TableConfig>) -> Result<(), String> {
+    let storage = get_local_storage().ok_or_else(|| "LocalStorage not available".to_string())?;
+    let json = serde_json::to_string(configs)
+        .map_err(|e| format!("Failed to serialize table configs: {}", e))?;
+    storage.set_item(TABLE_CONFIGS_KEY, &json)
+        .map_err(|e| format!("Failed to save to localStorage: {:?}", e))
+}
+
+pub fn load_table_configs() -> HashMap<String, TableConfig> {
+    get_local_storage()
+        .and_then(|storage| storage.get_item(TABLE_CONFIGS_KEY).ok().flatten())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+// Saves a playlist's tracks, dropping expired playlists and then the least recently saved ones to
+// stay within MAX_CACHED_PLAYLISTS, or to make room when the storage is full
+pub fn save_playlist_tracks(playlist_id: &str, name: &str, snapshot_id: &str, tracks: &TrackList, complete: bool) -> Result<(), String> {
+    let storage = get_local_storage().ok_or_else(|| "LocalStorage not available".to_string())?;
+    let now = js_sys::Date::now() as u64;
+    let stored_playlist = StoredPlaylist {
+        name: name.to_string(),
+        snapshot_id: snapshot_id.to_string(),
+        tracks: tracks.to_vec(),
+        positions: tracks.positions().to_vec(),
+        complete,
+        timestamp: now,
+    };
+
+    let json = serde_json::to_string(&stored_playlist)
+        .map_err(|e| format!("Failed to serialize playlist: {}", e))?;
+
+    // (playlist ID, when it was saved), oldest first
+    let mut cached = cached_playlists(&storage);
+    cached.retain(|(id, saved)| id != playlist_id && now.saturating_sub(*saved) <= CACHE_DURATION);
+    while cached.len() >= MAX_CACHED_PLAYLISTS {
+        cached.remove(0);
+    }
+    let result = loop {
+        match storage.set_item(&playlist_tracks_key(playlist_id), &json) {
+            Ok(()) => break Ok(()),
+            Err(_) if !cached.is_empty() => {
+                cached.remove(0);
+                remove_uncached_playlists(&storage, &cached);
+            }
+            Err(e) => break Err(format!("Failed to save to localStorage: {:?}", e)),
+        }
+    };
+    if result.is_ok() {
+        cached.push((playlist_id.to_string(), now));
+    }
+    remove_uncached_playlists(&storage, &cached);
+    if let Ok(json) = serde_json::to_string(&cached) {
+        let _ = storage.set_item(CACHED_PLAYLISTS_KEY, &json);
+    }
+    result
+}
+
+fn playlist_tracks_key(playlist_id: &str) -> String {
+    format!("{}{}", PLAYLIST_TRACKS_KEY_PREFIX, playlist_id)
+}
+
+// The cached playlists, oldest first. Playlists cached before there was a list are picked up from
+// their keys, as expired
+fn cached_playlists(storage: &web_sys::Storage) -> Vec<(String, u64)> {
+    if let Some(cached) = storage.get_item(CACHED_PLAYLISTS_KEY).ok().flatten().and_then(|json| serde_json::from_str(&json).ok()) {
+        return cached;
+    }
+    let length = storage.length().unwrap_or(0);
+    (0..length)
+        .filter_map(|index| storage.key(index).ok().flatten())
+        .filter_map(|key| key.strip_prefix(PLAYLIST_TRACKS_KEY_PREFIX).map(|id| (id.to_string(), 0)))
+        .collect()
+}
+
+// Removes the stored tracks of every playlist that's not in `cached`
+fn remove_uncached_playlists(storage: &web_sys::Storage, cached: &[(String, u64)]) {
+    let length = storage.length().unwrap_or(0);
+    let stale: Vec<String> = (0..length)
+        .filter_map(|index| storage.key(index).ok().flatten())
+        .filter(|key| {
+            key.strip_prefix(PLAYLIST_TRACKS_KEY_PREFIX)
+                .is_some_and(|id| !cached.iter().any(|(cached_id, _)| cached_id == id))
+        })
+        .collect();
+    for key in stale {
+        let _ = storage.remove_item(&key);
+    }
+}
+
+pub fn load_playlist_tracks(playlist_id: &str) -> Option<StoredPlaylist> {
+    let storage = get_local_storage()?;
+    let key = playlist_tracks_key(playlist_id);
+    let json = storage.get_item(&key).ok()??;
+
+    let stored_playlist: StoredPlaylist = serde_json::from_str(&json).ok()?;
+
+    // Same 24 hour validity as the liked songs cache
+    let now = js_sys::Date::now() as u64;
+    if now - stored_playlist.timestamp <= CACHE_DURATION {
+        Some(stored_playlist)
+    } else {
+        let _ = storage.remove_item(&key);
+        None
+    }
+}
+
 fn get_local_storage() -> Option<web_sys::Storage> {
     window()?.local_storage().ok()?
 }
@@ -1,20 +1,122 @@
 use once_cell::sync::Lazy;
-use std::sync::Mutex;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use super::layouts::LayoutPreset;
+use crate::storage::{load_layout_presets, load_table_configs};
+use super::track_table::TableConfig;
+use super::track_selection::TrackSelection;
+use crate::api_request::models::{PlaylistInfo, StoredTracks, TrackInfo};
+use crate::image_processing::collage::MosaicSettings;
+use crate::image_processing::export::CollageDetails;
+use crate::image_processing::styling::CollageStyle;
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum ViewMode {
     List,
     Grid,
+    Table,
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum GradientDirection {
     Diagonal,
     Horizontal,
     Vertical,
 }
 
-#[derive(Clone, Copy, PartialEq)]
+// The shape the collage's tiles are arranged in
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CollageLayout {
+    Grid,
+    Spiral, // Rings outwards from the middle
+    Mixed, // A few featured covers at twice the size
+    Hexagonal,
+    ColorWheel, // Hue around the circle, gray covers in the middle
+    Photomosaic, // A chosen picture recreated from the covers
+}
+
+impl CollageLayout {
+    pub const ALL: [CollageLayout; 6] = [
+        CollageLayout::Grid,
+        CollageLayout::Spiral,
+        CollageLayout::Mixed,
+        CollageLayout::Hexagonal,
+        CollageLayout::ColorWheel,
+        CollageLayout::Photomosaic,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CollageLayout::Grid => "Grid",
+            CollageLayout::Spiral => "Spiral",
+            CollageLayout::Mixed => "Mixed Sizes",
+            CollageLayout::Hexagonal => "Hexagons",
+            CollageLayout::ColorWheel => "Color Wheel",
+            CollageLayout::Photomosaic => "Photomosaic",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            CollageLayout::Grid => "Square tiles filled along the gradient",
+            CollageLayout::Spiral => "Square tiles in rings, starting in the middle",
+            CollageLayout::Mixed => "Square tiles with the covers of your most-included albums at twice the size",
+            CollageLayout::Hexagonal => "Hexagonal tiles in offset rows",
+            CollageLayout::ColorWheel => "A circle going round the hues, with gray covers in the middle and the most colorful outside",
+            CollageLayout::Photomosaic => "A picture of your choice recreated from the covers, as wide as the width setting",
+        }
+    }
+}
+
+// How the collage orders covers by color
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ColorSort {
+    Hue, // Average hue of the colorful pixels, with mostly white, gray and black covers last
+    DominantColor, // Hue of the largest k-means color cluster
+    Lightness,
+    HueThenLightness, // Hue bands, light to dark within each
+    SelfOrganizing, // Similar colors next to each other across and down, ignoring the gradient
+}
+
+impl ColorSort {
+    pub const ALL: [ColorSort; 5] = [
+        ColorSort::Hue,
+        ColorSort::DominantColor,
+        ColorSort::Lightness,
+        ColorSort::HueThenLightness,
+        ColorSort::SelfOrganizing,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ColorSort::Hue => "Average Hue",
+            ColorSort::DominantColor => "Dominant Color",
+            ColorSort::Lightness => "Lightness",
+            ColorSort::HueThenLightness => "Hue, then Lightness",
+            ColorSort::SelfOrganizing => "Similar Neighbors",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            ColorSort::Hue => "Colorful covers by their average hue, then white, gray and black ones",
+            ColorSort::DominantColor => "Covers by the hue of their main color, so a red and blue cover isn't sorted as purple",
+            ColorSort::Lightness => "Light to dark, by perceived lightness",
+            ColorSort::HueThenLightness => "Bands of similar hue, each going from light to dark",
+            ColorSort::SelfOrganizing => "Covers with similar colors are placed next to each other in both directions",
+        }
+    }
+
+    // Whether the starting hue changes anything
+    pub fn uses_hue(&self) -> bool {
+        matches!(self, ColorSort::Hue | ColorSort::DominantColor | ColorSort::HueThenLightness)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum StartingCorner {
     TopLeft,
     TopRight,
@@ -22,10 +124,329 @@ pub enum StartingCorner {
     BottomRight,
 }
 
+// How often artwork shared by several tracks, like an album's, appears in the collage
+#[derive(Clone, Copy, PartialEq)]
+pub enum ArtworkRepeats {
+    EveryTrack, // One tile per track
+    Once,
+    Weighted, // More tiles for artwork with more tracks, growing with the square root of the count
+}
+
+impl ArtworkRepeats {
+    // Tiles for artwork shared by `tracks` tracks
+    pub fn tiles(self, tracks: usize) -> usize {
+        match self {
+            ArtworkRepeats::EveryTrack => tracks,
+            ArtworkRepeats::Once => 1,
+            ArtworkRepeats::Weighted => (tracks as f32).sqrt().round().max(1.0) as usize,
+        }
+    }
+}
+
+// File format collages are downloaded in
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Png,
+    Jpeg,
+    WebP, // Lossless
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [ExportFormat::Png, ExportFormat::Jpeg, ExportFormat::WebP];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Png => "PNG",
+            ExportFormat::Jpeg => "JPEG",
+            ExportFormat::WebP => "WebP",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Png => "png",
+            ExportFormat::Jpeg => "jpg",
+            ExportFormat::WebP => "webp",
+        }
+    }
+}
+
+// Period Spotify computes top tracks and artists over
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TimeRange {
+    Short,
+    Medium,
+    Long,
+}
+
+impl TimeRange {
+    pub const ALL: [TimeRange; 3] = [TimeRange::Short, TimeRange::Medium, TimeRange::Long];
+
+    // Value for the top items endpoints' time_range parameter
+    pub fn api_value(self) -> &'static str {
+        match self {
+            TimeRange::Short => "short_term",
+            TimeRange::Medium => "medium_term",
+            TimeRange::Long => "long_term",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TimeRange::Short => "last 4 weeks",
+            TimeRange::Medium => "last 6 months",
+            TimeRange::Long => "all time",
+        }
+    }
+}
+
+// Where the collage's artwork comes from; several can be combined
+#[derive(Clone, PartialEq)]
+pub enum CollageSource {
+    LikedSongs,
+    Playlist(String), // Spotify playlist ID
+    TopTracks(TimeRange),
+    TopArtists(TimeRange), // Artist images rather than album art
+    RecentlyPlayed,
+}
+
+impl CollageSource {
+    pub fn label(&self) -> String {
+        match self {
+            CollageSource::LikedSongs => "Liked Songs".to_string(),
+            CollageSource::Playlist(_) => "Playlist".to_string(),
+            CollageSource::TopTracks(range) => format!("Top tracks ({})", range.label()),
+            CollageSource::TopArtists(range) => format!("Top artists ({})", range.label()),
+            CollageSource::RecentlyPlayed => "Recently played".to_string(),
+        }
+    }
+}
+
+static NEXT_TRACK_LIST_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+// Tracks of a view, shared so frames don't copy them. Every edit gives the list a new generation,
+// unique across views, so caches built from it (search matches, sort order) can tell they're stale
+#[derive(Clone, Default)]
+pub struct TrackList {
+    tracks: Arc<Vec<TrackInfo>>,
+    // Each track's position in its playlist, counting unavailable items that aren't shown. Empty
+    // when the tracks are the whole list in order
+    positions: Arc<Vec<usize>>,
+    generation: u64, // 0 only for the empty default list
+}
+
+impl TrackList {
+    pub fn new(tracks: Vec<TrackInfo>) -> Self {
+        Self::with_positions(tracks, Vec::new())
+    }
+
+    pub fn with_positions(tracks: Vec<TrackInfo>, positions: Vec<usize>) -> Self {
+        TrackList {
+            tracks: Arc::new(tracks),
+            positions: Arc::new(positions),
+            generation: NEXT_TRACK_LIST_GENERATION.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn position(&self, index: usize) -> usize {
+        self.positions.get(index).copied().unwrap_or(index)
+    }
+
+    pub fn positions(&self) -> &[usize] {
+        &self.positions
+    }
+
+    // Edits the tracks in place, copying them first if another frame still holds them. Only for
+    // lists without positions, which the edit couldn't keep in step
+    pub fn make_mut(&mut self) -> &mut Vec<TrackInfo> {
+        self.generation = NEXT_TRACK_LIST_GENERATION.fetch_add(1, Ordering::Relaxed);
+        Arc::make_mut(&mut self.tracks)
+    }
+
+    // Moves the item at playlist position `from` before the one at `insert_before`, the way Spotify
+    // reorders, shifting everything in between
+    pub fn move_item(&mut self, from: usize, insert_before: usize) {
+        let target = if insert_before > from { insert_before - 1 } else { insert_before };
+        let mut items: Vec<(usize, TrackInfo)> = self.tracks
+            .iter()
+            .enumerate()
+            .map(|(index, track)| {
+                let position = match self.position(index) {
+                    position if position == from => target,
+                    position if from < position && position < insert_before => position - 1,
+                    position if insert_before <= position && position < from => position + 1,
+                    position => position,
+                };
+                (position, track.clone())
+            })
+            .collect();
+        items.sort_by_key(|(position, _)| *position);
+        let (positions, tracks) = items.into_iter().unzip();
+        *self = Self::with_positions(tracks, positions);
+    }
+
+    // Removes every occurrence of the tracks, closing the gaps they leave in the positions
+    pub fn remove_uris(&mut self, uris: &[String]) {
+        let removed: Vec<usize> = (0..self.tracks.len())
+            .filter(|&index| uris.contains(&self.tracks[index].uri))
+            .map(|index| self.position(index))
+            .collect();
+        let (positions, tracks) = self.tracks
+            .iter()
+            .enumerate()
+            .filter(|(_, track)| !uris.contains(&track.uri))
+            .map(|(index, track)| {
+                let position = self.position(index);
+                (position - removed.iter().filter(|&&r| r < position).count(), track.clone())
+            })
+            .unzip();
+        *self = Self::with_positions(tracks, positions);
+    }
+}
+
+impl Deref for TrackList {
+    type Target = [TrackInfo];
+
+    fn deref(&self) -> &[TrackInfo] {
+        &self.tracks
+    }
+}
+
+#[derive(Clone)]
+pub struct PlaylistWindow {
+    pub id: String, // Spotify playlist ID
+    pub name: String,
+    pub tracks: TrackList,
+    pub complete: bool, // Every item is loaded; the playlist object only embeds the first 100
+    pub snapshot_id: String, // Playlist version the tracks came from, sent with edits
+    pub search_text: String,
+    pub view_mode: ViewMode,
+    pub open: bool,
+    pub pos: (f32, f32),
+    pub size: (f32, f32),
+}
+
+impl PlaylistWindow {
+    pub fn new(id: String, name: String) -> Self {
+        PlaylistWindow {
+            id,
+            name,
+            tracks: TrackList::default(),
+            complete: false,
+            snapshot_id: String::new(),
+            search_text: String::new(),
+            view_mode: ViewMode::List,
+            open: true,
+            pos: (500.0, 100.0),
+            size: (600.0, 400.0),
+        }
+    }
+}
+
+// Details typed into the playlist dialog; without an ID they create a new playlist
+#[derive(Clone, Default)]
+pub struct PlaylistDraft {
+    pub id: Option<String>,
+    pub name: String,
+    pub description: String,
+    pub public: bool,
+    pub collaborative: bool,
+}
+
+impl PlaylistDraft {
+    pub fn edit(playlist: &PlaylistInfo) -> Self {
+        PlaylistDraft {
+            id: Some(playlist.id.clone()),
+            name: playlist.name.clone(),
+            description: playlist.description.clone(),
+            public: playlist.public,
+            collaborative: playlist.collaborative,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SearchCategory {
+    Tracks,
+    Albums,
+    Artists,
+    Playlists,
+}
+
+impl SearchCategory {
+    pub const ALL: [SearchCategory; 4] = [
+        SearchCategory::Tracks,
+        SearchCategory::Albums,
+        SearchCategory::Artists,
+        SearchCategory::Playlists,
+    ];
+
+    // Value for the search endpoint's type parameter; the response keys are the plurals
+    pub fn api_type(self) -> &'static str {
+        match self {
+            SearchCategory::Tracks => "track",
+            SearchCategory::Albums => "album",
+            SearchCategory::Artists => "artist",
+            SearchCategory::Playlists => "playlist",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SearchCategory::Tracks => "Tracks",
+            SearchCategory::Albums => "Albums",
+            SearchCategory::Artists => "Artists",
+            SearchCategory::Playlists => "Playlists",
+        }
+    }
+}
+
+// One page of catalog search results; albums, artists and playlists reuse TrackInfo so the track views can show them
+#[derive(Clone, Default)]
+pub struct CatalogPage {
+    pub items: Arc<Vec<TrackInfo>>,
+    pub offset: u32,
+    pub total: u32,
+}
+
+#[derive(Clone, Default)]
+pub struct CatalogSearch {
+    pub query: String, // Query the current results belong to
+    pub tracks: CatalogPage,
+    pub albums: CatalogPage,
+    pub artists: CatalogPage,
+    pub playlists: CatalogPage,
+    pub loading: bool,
+}
+
+impl CatalogSearch {
+    pub fn page(&self, category: SearchCategory) -> &CatalogPage {
+        match category {
+            SearchCategory::Tracks => &self.tracks,
+            SearchCategory::Albums => &self.albums,
+            SearchCategory::Artists => &self.artists,
+            SearchCategory::Playlists => &self.playlists,
+        }
+    }
+
+    pub fn page_mut(&mut self, category: SearchCategory) -> &mut CatalogPage {
+        match category {
+            SearchCategory::Tracks => &mut self.tracks,
+            SearchCategory::Albums => &mut self.albums,
+            SearchCategory::Artists => &mut self.artists,
+            SearchCategory::Playlists => &mut self.playlists,
+        }
+    }
+}
+
 pub struct AppState {
     pub collage_image: Option<Vec<u8>>, // Store the generated collage image data
     pub username: Option<String>,
-    pub saved_tracks: Vec<(String, String, String, String)>, // (track name, artist name, image url, uri)
+    pub saved_tracks: TrackList,
     pub tracks_per_load: i32, // Number of tracks to load at a time
     pub loaded_tracks_count: i32, // Number of tracks currently loaded
     pub show_tracks: bool,
@@ -45,6 +466,8 @@ pub struct AppState {
     pub music_player_window_pos: (f32, f32), // Default position for Music Player window
     pub collage_window_open: bool,
     pub collage_window_pos: (f32, f32), // Default position for Collage window
+    pub collage_window_size: (f32, f32),
+    pub music_player_window_size: (f32, f32),
     pub loading_message: String, // Status message for loading operations
     pub progress: f32, // Progress for the progress bar
     pub collage_loading: bool, // Loading state for collage generation
@@ -53,18 +476,60 @@ pub struct AppState {
     pub hue_shift: f32, // Add hue shift field
     pub gradient_direction: GradientDirection, // Add gradient direction field
     pub starting_corner: StartingCorner, // Add starting corner field
-    pub playlists: Vec<(String, String, String, String, i32)>, // (playlist name, owner, image url, id, total tracks)
+    pub color_sort: ColorSort,
+    pub collage_layout: CollageLayout,
+    pub mosaic_settings: MosaicSettings,
+    pub mosaic_target: Option<(String, Vec<u8>)>, // Name and encoded bytes of the photomosaic's picture
+    pub mosaic_artists: Vec<TrackInfo>, // Top artists whose images can be the picture, fetched when first needed
+    pub mosaic_artists_loading: bool,
+    pub collage_style: CollageStyle,
+    pub caption_title: String,
+    pub caption_username: bool,
+    pub caption_date_range: bool, // The top items' time ranges, or today's date for other sources
+    pub collage_details: Option<CollageDetails>, // What the current collage shows, for its metadata and image map
+    pub export_format: ExportFormat,
+    pub jpeg_quality: u8,
+    pub export_image_map: bool, // Download JSON and HTML linking each tile to Spotify along with the collage
+    pub collage_sources: Vec<CollageSource>, // Combined, without duplicate tracks
+    pub collage_image_count: usize, // Images the collage being generated downloads
+    pub collage_generation: u32, // Bumped to start or cancel a collage; older generations stop
+    pub artwork_repeats: ArtworkRepeats,
+    pub cover_target: Option<String>, // Playlist the collage is uploaded to as its cover
+    pub cover_uploading: bool,
+    pub playlists: Vec<PlaylistInfo>,
+    pub playlist_draft: Option<PlaylistDraft>, // Playlist being created or edited in the details dialog
     pub show_playlists: bool,
     pub playlists_window_open: bool,
     pub playlists_window_size: (f32, f32),
     pub playlists_window_pos: (f32, f32),
     pub show_playlist_tracks_window: bool,
     pub playlist_tracks_window_open: bool,
-    pub playlist_windows: Vec<(String, String, Vec<(String, String, String, String)>, ViewMode, bool, (f32, f32))>, // Add this new field
+    pub playlist_windows: Vec<PlaylistWindow>,
     pub user_id: Option<String>, // Add this new field
     pub settings_initialized: bool, // New field to track initialization
     pub original_name: String,      // New field to store the original player name
     pub sidebar_open: bool, // needed so 'sidebar_open' is recognized
+    pub dock_mode: bool, // Show windows as tabs in a tiled dock instead of floating windows
+    pub layout_presets: Vec<LayoutPreset>,
+    pub layout_generation: u32, // Bumped when a preset is applied so windows are recreated at the preset sizes
+    pub new_layout_name: String,
+    pub layout_import_text: String,
+    pub layout_import_error: Option<String>, // Why the pasted layouts couldn't be imported
+    pub scroll_offsets: HashMap<String, f32>, // Vertical scroll offset per track view, keyed like "playlist:<id>"
+    pub pending_scroll_offsets: HashMap<String, f32>, // Restored offsets waiting for their view's content to load
+    pub table_configs: HashMap<String, TableConfig>, // Table view columns and sort order, keyed like the scroll offsets
+    pub search_window_open: bool,
+    pub search_window_pos: (f32, f32),
+    pub search_window_size: (f32, f32),
+    pub catalog_query: String, // Text in the Search window's box
+    pub catalog: CatalogSearch,
+    pub catalog_category: SearchCategory,
+    pub catalog_view_mode: ViewMode,
+    pub liked_tracks: HashMap<String, bool>, // Whether each track ID is in Liked Songs, as far as we know
+    pub requested_liked_checks: HashSet<String>, // IDs already queued for or sent to /me/tracks/contains
+    pub queued_liked_checks: Vec<String>,
+    pub unsaved_library: Option<StoredTracks>, // Storage cache of the library edited by likes, until the session saver writes it
+    pub track_selections: HashMap<String, TrackSelection>, // Selected tracks per view, keyed like the scroll offsets
 }
 
 impl Default for AppState {
@@ -91,11 +556,17 @@ impl Default for AppState {
             .and_then(|val| val.parse().ok())
             .unwrap_or(true);
 
+        let dock_mode = local_storage
+            .as_ref()
+            .and_then(|storage| storage.get_item("dock_mode").ok().flatten())
+            .and_then(|val| val.parse().ok())
+            .unwrap_or(false);
+
 
         AppState { 
             collage_image: None,
             username: None,
-            saved_tracks: Vec::new(),
+            saved_tracks: TrackList::default(),
             tracks_per_load,
             loaded_tracks_count: 0,
             show_tracks: false,
@@ -115,6 +586,8 @@ impl Default for AppState {
             music_player_window_pos: (1069.0, 30.0),
             collage_window_open: false,
             collage_window_pos: (650.0, 30.0),
+            collage_window_size: (800.0, 600.0),
+            music_player_window_size: (300.0, 400.0),
             loading_message: String::new(),
             progress: 0.0,
             collage_loading: false,
@@ -123,7 +596,28 @@ impl Default for AppState {
             hue_shift: 0.0, // Default hue shift value
             gradient_direction: GradientDirection::Diagonal, // Default gradient direction
             starting_corner: StartingCorner::TopLeft, // Default starting corner
+            color_sort: ColorSort::Hue,
+            collage_layout: CollageLayout::Grid,
+            mosaic_settings: MosaicSettings::default(),
+            mosaic_target: None,
+            mosaic_artists: Vec::new(),
+            mosaic_artists_loading: false,
+            collage_style: CollageStyle::default(),
+            caption_title: String::new(),
+            caption_username: false,
+            caption_date_range: false,
+            collage_details: None,
+            export_format: ExportFormat::Png,
+            jpeg_quality: 90,
+            export_image_map: false,
+            collage_sources: vec![CollageSource::LikedSongs],
+            collage_image_count: 0,
+            collage_generation: 0,
+            artwork_repeats: ArtworkRepeats::Once,
+            cover_target: None,
+            cover_uploading: false,
             playlists: Vec::new(),
+            playlist_draft: None,
             show_playlists: false,
             playlists_window_open: false,
             playlists_window_size: (400.0, 500.0),
@@ -135,11 +629,39 @@ impl Default for AppState {
             settings_initialized: false,                  // Initialize new fields
             original_name: String::new(),                 // Initialize new fields
             sidebar_open: true, // Set to true by default
+            dock_mode,
+            layout_presets: load_layout_presets(),
+            layout_generation: 0,
+            new_layout_name: String::new(),
+            layout_import_text: String::new(),
+            layout_import_error: None,
+            scroll_offsets: HashMap::new(),
+            pending_scroll_offsets: HashMap::new(),
+            table_configs: load_table_configs(),
+            search_window_open: false,
+            search_window_pos: (650.0, 30.0),
+            search_window_size: (600.0, 500.0),
+            catalog_query: String::new(),
+            catalog: CatalogSearch::default(),
+            catalog_category: SearchCategory::Tracks,
+            catalog_view_mode: ViewMode::List,
+            liked_tracks: HashMap::new(),
+            requested_liked_checks: HashSet::new(),
+            queued_liked_checks: Vec::new(),
+            unsaved_library: None,
+            track_selections: HashMap::new(),
         }
     }
 }
 
 impl AppState {
+    // Stops the collage being generated; its task notices the next time it checks
+    pub fn cancel_collage(&mut self) {
+        self.collage_generation += 1;
+        self.collage_loading = false;
+        self.progress = 0.0;
+    }
+
     pub fn reset_areas(&mut self) {
         // Reset window-related states to default
         self.tracks_window_size = (800.0, 600.0); // Default size
@@ -149,9 +671,30 @@ impl AppState {
         self.liked_songs_window_pos = (238.0, 30.0);
         self.music_player_window_pos = (1069.0, 30.0);
         self.collage_window_pos = (650.0, 30.0);
+        self.search_window_pos = (650.0, 30.0);
         // Add any additional reset logic as needed
     }
 
+    // Hands out a restored scroll offset once the view has content to scroll, so it isn't clamped away
+    pub fn take_pending_scroll_offset(&mut self, key: &str, content_ready: bool) -> Option<f32> {
+        if content_ready {
+            self.pending_scroll_offsets.remove(key)
+        } else {
+            None
+        }
+    }
+
+    // Liked status of a track, queuing a batched lookup the first time an unknown track is shown
+    pub fn track_liked(&mut self, id: &str) -> Option<bool> {
+        if let Some(&liked) = self.liked_tracks.get(id) {
+            return Some(liked);
+        }
+        if self.requested_liked_checks.insert(id.to_string()) {
+            self.queued_liked_checks.push(id.to_string());
+        }
+        None
+    }
+
     pub fn constrain_to_central_panel(&self, ctx: &egui::Context) -> egui::Rect {
         let screen_rect = ctx.screen_rect();
         let sidebar_width = if self.sidebar_open { 180.0 } else { 0.0 }; // Only reserve space if sidebar is open
@@ -163,6 +706,18 @@ impl AppState {
     }
 }
 
+// Window sizes are saved as the whole window, but egui sizes windows by their contents, so the
+// frame and title bar come off before a saved size is handed back to a window
+pub fn window_content_size(ctx: &egui::Context, size: (f32, f32)) -> egui::Vec2 {
+    let style = ctx.style();
+    let frame = egui::Frame::window(&style);
+    let title_height = ctx.fonts(|fonts| fonts.row_height(&egui::TextStyle::Heading.resolve(&style)))
+        + frame.inner_margin.top
+        + frame.inner_margin.bottom;
+    let margins = frame.outer_margin.sum() + frame.inner_margin.sum() + egui::vec2(0.0, title_height);
+    (egui::vec2(size.0, size.1) - margins).max(egui::Vec2::ZERO)
+}
+
 pub static APP_STATE: Lazy<Mutex<AppState>> = Lazy::new(|| Mutex::new(AppState::default()));
 
 pub fn set_username(name: String) {
@@ -180,3 +735,47 @@ pub fn set_user_id(id: String) {
     let mut state = APP_STATE.lock().unwrap();
     state.user_id = Some(id);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Tracks named by letter at the given playlist positions
+    fn track_list(items: &[(usize, &str)]) -> TrackList {
+        let (positions, tracks) = items
+            .iter()
+            .map(|&(position, name)| (position, TrackInfo { uri: name.to_string(), ..Default::default() }))
+            .unzip();
+        TrackList::with_positions(tracks, positions)
+    }
+
+    fn items(list: &TrackList) -> Vec<(usize, &str)> {
+        list.iter().enumerate().map(|(index, track)| (list.position(index), track.uri.as_str())).collect()
+    }
+
+    #[test]
+    fn move_item_skips_over_hidden_positions() {
+        // Position 1 is an unavailable item that isn't shown
+        let mut list = track_list(&[(0, "a"), (2, "b"), (3, "c")]);
+        list.move_item(3, 0);
+        assert_eq!(items(&list), vec![(0, "c"), (1, "a"), (3, "b")]);
+        list.move_item(0, 4);
+        assert_eq!(items(&list), vec![(0, "a"), (2, "b"), (3, "c")]);
+    }
+
+    #[test]
+    fn remove_uris_closes_gaps() {
+        let mut list = track_list(&[(0, "a"), (2, "b"), (3, "c"), (5, "b")]);
+        list.remove_uris(&["b".to_string()]);
+        assert_eq!(items(&list), vec![(0, "a"), (2, "c")]);
+    }
+
+    #[test]
+    fn edits_change_the_generation() {
+        let mut list = TrackList::new(vec![TrackInfo::default()]);
+        let generation = list.generation();
+        list.make_mut().clear();
+        assert_ne!(list.generation(), generation);
+        assert_eq!(list.position(0), 0);
+    }
+}
@@ -1,37 +1,19 @@
-use super::app_state::{APP_STATE, GradientDirection, StartingCorner}; // Import enums from app_state
-use crate::image_processing::collage::create_collage;
-use egui::{Context, Color32, ColorImage, load::SizedTexture, ProgressBar}; // Add Color32
+use super::app_state::{window_content_size, APP_STATE, AppState, ArtworkRepeats, CollageLayout, CollageSource, ColorSort, ExportFormat, GradientDirection, StartingCorner, TimeRange}; // Import enums from app_state
+use crate::api_request::token::get_token;
+use crate::api_request::models::PlaylistInfo;
+use crate::image_processing::collage::{grid_dimensions, mosaic_dimensions, CollageSettings};
+use crate::image_processing::styling::{CaptionPosition, CollageBackground, CollageStyle};
+use crate::image_processing::worker::{compose_collage, CollageJob};
+use crate::image_processing::cover::encode_playlist_cover;
+use crate::image_processing::export::{encode_collage, CollageDetails};
+use crate::utils::log_error;
+use crate::api_request::artwork::fetch_artwork;
+use crate::api_request::collage_sources::{fetch_collage_tracks, fetch_top_artists, group_by_artwork, resolve_artwork_sizes};
+use egui::{Context, Ui, Color32, ColorImage, load::SizedTexture, ProgressBar}; // Add Color32
 use wasm_bindgen_futures::spawn_local;
-use web_sys::{Blob, Url};
-use wasm_bindgen::JsCast;
-use std::io::Cursor;
+use crate::utils::{download_file, pick_file};
 use egui::CursorIcon;
 
-fn download_collage(image_data: &[u8]) {
-    // Create a Blob from the image data
-    let array = js_sys::Uint8Array::from(image_data);
-    let blob_parts = js_sys::Array::new();
-    blob_parts.push(&array);
-    
-    if let Ok(blob) = Blob::new_with_u8_array_sequence(&blob_parts) {
-        if let Ok(url) = Url::create_object_url_with_blob(&blob) {
-            if let Some(window) = web_sys::window() {
-                if let Some(document) = window.document() {
-                    if let Some(link) = document.create_element("a").ok() {
-                        let link = link.dyn_into::<web_sys::HtmlAnchorElement>().unwrap();
-                        link.set_href(&url);
-                        link.set_download("collage.png");
-                        link.click();
-                        
-                        // Clean up
-                        let _ = Url::revoke_object_url(&url);
-                    }
-                }
-            }
-        }
-    }
-}
-
 fn hsv_to_rgb(h: f32, s: f32, v: f32) -> (u8, u8, u8) {
     let c = v * s;
     let x = c * (1.0 - ((h / 60.0) % 2.0 - 1.0).abs());
@@ -62,184 +44,724 @@ pub fn show_collage_window(ctx: &Context) {
 
     let mut collage_window_open = state.collage_window_open;
     let collage_position = state.collage_window_pos;
+    let collage_size = state.collage_window_size;
     let constrain_rect = state.constrain_to_central_panel(ctx);
-    let collage_loading = state.collage_loading;
-    let progress = state.progress;
-    let saved_tracks = state.saved_tracks.clone();
-    let collage_image = state.collage_image.clone();
+    let layout_generation = state.layout_generation;
     drop(state);
 
     let window = egui::Window::new("Collage")
+        .id(egui::Id::new(("collage_window", layout_generation)))
         .open(&mut collage_window_open)
         .current_pos(collage_position)
-        .default_size((800.0, 600.0))
+        .default_size(window_content_size(ctx, collage_size))
         .resizable(true)
         .constrain_to(constrain_rect)
         .show(ctx, |ui| {
-            ui.label("Create a collage from your liked songs' album artwork");
+            show_collage_contents(ui);
+        });
 
-            ui.collapsing("Collage Settings", |ui| {
+    let mut state = APP_STATE.lock().unwrap();
+    state.collage_window_open = collage_window_open;
+    if let Some(resp) = window {
+        let r = resp.response.rect;
+        state.collage_window_pos = (r.min.x, r.min.y);
+        if resp.inner.is_some() {
+            state.collage_window_size = (r.width(), r.height());
+        }
+    }
+}
+
+// Renders the collage generator body, shared by the floating window and the dock tab
+pub fn show_collage_contents(ui: &mut Ui) {
+    let state = APP_STATE.lock().unwrap();
+    let collage_loading = state.collage_loading;
+    let progress = state.progress;
+    let track_count = state.collage_image_count;
+    let loading_message = state.loading_message.clone();
+    let has_sources = !state.collage_sources.is_empty();
+    let needs_picture = state.collage_layout == CollageLayout::Photomosaic && state.mosaic_target.is_none();
+    let collage_image = state.collage_image.clone();
+    drop(state);
+
+    ui.label("Create a collage from the album artwork of your music");
+    show_source_picker(ui);
+
+    ui.collapsing("Collage Settings", |ui| {
+        let mut state = APP_STATE.lock().unwrap();
+        // Add input fields for width and height
+        ui.horizontal(|ui| {
+            ui.label("Width:");
+            ui.add(egui::DragValue::new(&mut state.collage_width).range(100..=3840));
+            ui.label("Height:");
+            ui.add(egui::DragValue::new(&mut state.collage_height).range(100..=2160));
+        });
+
+        // The color wheel orders covers by hue and colorfulness itself, and photomosaics by their picture
+        let layout = state.collage_layout;
+        if !matches!(layout, CollageLayout::ColorWheel | CollageLayout::Photomosaic) {
+            ui.horizontal(|ui| {
+                ui.label("Color Sort:");
+                let color_sort = &mut state.color_sort;
+                egui::ComboBox::from_id_salt("collage_color_sort")
+                    .selected_text(color_sort.label())
+                    .show_ui(ui, |ui| {
+                        for sort in ColorSort::ALL {
+                            ui.selectable_value(color_sort, sort, sort.label()).on_hover_text(sort.description());
+                        }
+                    })
+                    .response
+                    .on_hover_text(color_sort.description());
+            });
+        }
+
+        // Add slider for hue shift, which turns the color wheel
+        if (state.color_sort.uses_hue() && layout != CollageLayout::Photomosaic) || layout == CollageLayout::ColorWheel {
+            ui.horizontal(|ui| {
+                ui.label("Starting Hue:")
+                .on_hover_text("Choose the starting color of the rainbow gradient effect");
+                let hue_shift = &mut state.hue_shift;
+                ui.add(egui::Slider::new(hue_shift, 0.0..=360.0).text("degrees").show_value(false));
+                let (r, g, b) = hsv_to_rgb(*hue_shift, 1.0, 1.0);
+                let color = Color32::from_rgb(r, g, b);
+                ui.colored_label(color, format!("{:.0}°", hue_shift));
+            });
+        }
+
+        // Photomosaics reuse covers as their picture needs
+        if layout != CollageLayout::Photomosaic {
+            ui.horizontal(|ui| {
+                ui.label("Repeated Artwork:")
+                .on_hover_text("How often artwork shared by several tracks, like an album's, appears");
+                ui.selectable_value(&mut state.artwork_repeats, ArtworkRepeats::Once, "Once");
+                ui.selectable_value(&mut state.artwork_repeats, ArtworkRepeats::Weighted, "Weighted")
+                    .on_hover_text("Albums with more tracks get more tiles, without one tile per track");
+                ui.selectable_value(&mut state.artwork_repeats, ArtworkRepeats::EveryTrack, "Every Track");
+            });
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Layout:");
+            let layout = &mut state.collage_layout;
+            egui::ComboBox::from_id_salt("collage_layout")
+                .selected_text(layout.label())
+                .show_ui(ui, |ui| {
+                    for option in CollageLayout::ALL {
+                        ui.selectable_value(layout, option, option.label()).on_hover_text(option.description());
+                    }
+                })
+                .response
+                .on_hover_text(layout.description());
+        });
+
+        if state.collage_layout == CollageLayout::Photomosaic {
+            show_mosaic_settings(ui, &mut state);
+        }
+
+        // Only grids follow a gradient, and similar neighbors arranges the grid itself
+        if state.collage_layout == CollageLayout::Grid && state.color_sort != ColorSort::SelfOrganizing {
+            // Add options for gradient direction
+            ui.horizontal(|ui| {
+                ui.label("Gradient Direction:");
+                ui.selectable_value(&mut state.gradient_direction, GradientDirection::Diagonal, "Diagonal");
+                ui.selectable_value(&mut state.gradient_direction, GradientDirection::Horizontal, "Horizontal");
+                ui.selectable_value(&mut state.gradient_direction, GradientDirection::Vertical, "Vertical");
+            });
+
+            // Conditionally show options for starting corner or side
+            match state.gradient_direction {
+                GradientDirection::Diagonal => {
+                    ui.horizontal(|ui| {
+                        ui.label("Starting Corner:");
+                        ui.selectable_value(&mut state.starting_corner, StartingCorner::TopLeft, "Top Left");
+                        ui.selectable_value(&mut state.starting_corner, StartingCorner::TopRight, "Top Right");
+                        ui.selectable_value(&mut state.starting_corner, StartingCorner::BottomLeft, "Bottom Left");
+                        ui.selectable_value(&mut state.starting_corner, StartingCorner::BottomRight, "Bottom Right");
+                    });
+                },
+                GradientDirection::Horizontal => {
+                    ui.horizontal(|ui| {
+                        ui.label("Starting Side:");
+                        ui.selectable_value(&mut state.starting_corner, StartingCorner::TopLeft, "Top");
+                        ui.selectable_value(&mut state.starting_corner, StartingCorner::TopRight, "Bottom");
+                    });
+                },
+                GradientDirection::Vertical => {
+                    ui.horizontal(|ui| {
+                        ui.label("Starting Side:");
+                        ui.selectable_value(&mut state.starting_corner, StartingCorner::TopLeft, "Left");
+                        ui.selectable_value(&mut state.starting_corner, StartingCorner::BottomLeft, "Right");
+                    });
+                },
+            }
+        }
+
+        ui.separator();
+        show_style_settings(ui, &mut state);
+        drop(state);
+    });
+
+    // Show preview if we have a generated image
+    if let Some(image_data) = &collage_image {
+        show_export(ui, image_data);
+        show_cover_upload(ui, image_data);
+        
+        // Convert image data to egui texture for preview
+        if let Ok(img) = image::load_from_memory(image_data) {
+            let size = [img.width() as _, img.height() as _];
+            let pixels = img.to_rgba8();
+            let pixels = pixels.as_flat_samples();
+            let color_image = ColorImage::from_rgba_unmultiplied(size, pixels.as_slice());
+            let texture = ui.ctx().load_texture(
+                "collage-preview",
+                color_image,
+                Default::default()
+            );
+            
+            // Calculate preview size to fit window while maintaining aspect ratio
+            let max_width = ui.available_width().min(600.0);
+            let aspect_ratio = size[0] as f32 / size[1] as f32;
+            let preview_size = [max_width, max_width / aspect_ratio];
+            
+            ui.image(SizedTexture::new(texture.id(), preview_size));
+        }
+    }
+    
+    // Generating again while a collage is loading restarts it with the current settings
+    let generate_label = if collage_loading { "Restart Generation" } else { "Generate New Collage" };
+    ui.horizontal(|ui| {
+        let generate = ui.add_enabled(has_sources && !needs_picture, egui::Button::new(generate_label))
+            .on_disabled_hover_text(if needs_picture { "Choose a picture for the photomosaic first" } else { "Choose at least one source" });
+        if generate.on_hover_cursor(CursorIcon::PointingHand).clicked() {
+            start_collage_generation(false);
+        }
+        if collage_loading && ui.button("Cancel").on_hover_cursor(CursorIcon::PointingHand).clicked() {
+            APP_STATE.lock().unwrap().cancel_collage();
+        }
+    });
+    
+    if collage_loading {
+        ui.label(&loading_message);
+        let progress_text = format!("{}/{}", (progress * track_count as f32).round() as i32, track_count);
+        ui.add(ProgressBar::new(progress).animate(true).text(progress_text));
+    }
+}
+
+// Starts generating a collage from the current settings, superseding one that's still loading.
+// For a playlist cover it's made square at the shorter side, padded out so it's exactly square
+fn start_collage_generation(square: bool) {
+    let mut state = APP_STATE.lock().unwrap();
+    let sources = state.collage_sources.clone();
+    let (width, height) = if square {
+        let side = state.collage_width.min(state.collage_height);
+        (side, side)
+    } else {
+        (state.collage_width, state.collage_height)
+    };
+    let mut style = CollageStyle { caption: caption_lines(&state), ..state.collage_style.clone() };
+    style.fill_size |= square;
+    let settings = CollageSettings {
+        width,
+        height,
+        hue_shift: state.hue_shift,
+        gradient_direction: state.gradient_direction,
+        starting_corner: state.starting_corner,
+        color_sort: state.color_sort,
+        layout: state.collage_layout,
+        mosaic: state.mosaic_settings,
+        style,
+    };
+    // A photomosaic picks the covers for its cells itself, so each one is fetched once at the cell size
+    let is_mosaic = settings.layout == CollageLayout::Photomosaic;
+    let repeats = if is_mosaic { ArtworkRepeats::Once } else { state.artwork_repeats };
+    let mosaic_target = state.mosaic_target.as_ref().filter(|_| is_mosaic).map(|(_, bytes)| bytes.clone());
+    let source = sources_summary(&sources, &state.playlists);
+    
+    // Set collage_loading to true
+    state.collage_generation += 1;
+    let generation = state.collage_generation;
+    state.collage_loading = true;
+    state.collage_image_count = 0;
+    state.progress = 0.0;
+    // A collage of just one playlist is most likely meant as its cover
+    if let [CollageSource::Playlist(id)] = sources.as_slice() {
+        state.cover_target = Some(id.clone());
+    }
+    drop(state);
+
+    // A newer generation or closing the window stops this one. Closing leaves the loading state
+    // to reset, while a newer generation has already taken it over
+    let is_cancelled = move || {
+        let mut state = APP_STATE.lock().unwrap();
+        if state.collage_generation != generation {
+            return true;
+        }
+        if !state.collage_window_open {
+            state.cancel_collage();
+            return true;
+        }
+        false
+    };
+    
+    spawn_local(async move {
+        let token = get_token().unwrap_or_default();
+        let tracks = fetch_collage_tracks(&token, &sources).await;
+        if is_cancelled() {
+            return;
+        }
+        let mut artworks = group_by_artwork(&tracks);
+        resolve_artwork_sizes(&token, &mut artworks).await;
+        if is_cancelled() {
+            return;
+        }
+
+        // The tile size is known before downloading, so each artwork is fetched once at the
+        // resolution its tiles need
+        let tile_counts: Vec<usize> = artworks.iter().map(|artwork| repeats.tiles(artwork.track_count)).collect();
+        let tile_size = if is_mosaic {
+            let target_size = mosaic_target
+                .as_deref()
+                .and_then(|bytes| image::ImageReader::new(std::io::Cursor::new(bytes)).with_guessed_format().ok()?.into_dimensions().ok())
+                .unwrap_or((width, height));
+            mosaic_dimensions(target_size.0, target_size.1, settings.mosaic.columns, width, height).2
+        } else {
+            grid_dimensions(tile_counts.iter().sum(), width, height).2
+        };
+        let urls: Vec<String> = artworks
+            .iter()
+            .map(|artwork| artwork.image_for(tile_size).unwrap_or_default().to_string())
+            .collect();
+        let total_images = urls.len();
+        
+        // Update loading message
+        {
+            let mut state = APP_STATE.lock().unwrap();
+            state.collage_image_count = total_images;
+            state.loading_message = format!("Loading {} images from {} tracks...", total_images, tracks.len());
+        }
+        
+        // Download and process album artwork
+        let on_loaded = |loaded_count: usize| {
+            let mut state = APP_STATE.lock().unwrap();
+            state.progress = loaded_count as f32 / total_images as f32;
+        };
+        let Some(downloaded) = fetch_artwork(&urls, is_cancelled, on_loaded).await else {
+            return;
+        };
+        let mut images = Vec::new();
+        let mut tiles = Vec::new();
+        let mut image_urls = Vec::new();
+        let mut track_counts = Vec::new();
+        let mut image_artworks = Vec::new();
+        for (((image, count), url), artwork) in downloaded.into_iter().zip(tile_counts).zip(urls).zip(&artworks) {
+            if let Some(image) = image {
+                images.push(image);
+                tiles.push(count);
+                image_urls.push(url);
+                track_counts.push(artwork.track_count);
+                image_artworks.push((artwork.name.clone(), artwork.key.clone()));
+            }
+        }
+        
+        // Only proceed if we have images
+        if images.is_empty() {
+            let mut state = APP_STATE.lock().unwrap();
+            state.progress = 0.0;
+            state.collage_loading = false; // Reset collage_loading
+            return;
+        }
+        
+        // Arrange the collage in the worker, which reports each image it analyses and draws
+        {
+            let mut state = APP_STATE.lock().unwrap();
+            state.collage_image_count = tiles.iter().sum();
+            state.progress = 0.0;
+            state.loading_message = "Arranging collage...".to_string();
+        }
+        let job = CollageJob { settings, tiles, urls: image_urls, track_counts, mosaic_target };
+        let on_progress = |done: usize, total: usize| {
+            let mut state = APP_STATE.lock().unwrap();
+            state.progress = done as f32 / total.max(1) as f32;
+        };
+        let Some((collage, tile_map)) = compose_collage(job, images, is_cancelled, on_progress).await else {
+            // Failures are already logged; cancelled collages leave the state to whoever cancelled them
+            if !is_cancelled() {
                 let mut state = APP_STATE.lock().unwrap();
-                // Add input fields for width and height
-                ui.horizontal(|ui| {
-                    ui.label("Width:");
-                    ui.add(egui::DragValue::new(&mut state.collage_width).range(100..=3840));
-                    ui.label("Height:");
-                    ui.add(egui::DragValue::new(&mut state.collage_height).range(100..=2160));
-                });
+                state.progress = 0.0;
+                state.collage_loading = false;
+            }
+            return;
+        };
+        let details = CollageDetails {
+            source,
+            tracks: tracks.iter().map(|track| format!("{} - {}", track.artists, track.name)).collect(),
+            artworks: image_artworks,
+            tiles: tile_map,
+        };
+        
+        // Update loading state
+        let mut state = APP_STATE.lock().unwrap();
+        state.collage_image = Some(collage);
+        state.collage_details = Some(details);
+        state.progress = 0.0;
+        state.collage_loading = false; // Reset collage_loading
+    });
+}
 
-                // Add slider for hue shift
-                ui.horizontal(|ui| {
-                    ui.label("Starting Hue:")
-                    .on_hover_text("Choose the starting color of the rainbow gradient effect");
-                    let hue_shift = &mut state.hue_shift;
-                    ui.add(egui::Slider::new(hue_shift, 0.0..=360.0).text("degrees").show_value(false));
-                    let (r, g, b) = hsv_to_rgb(*hue_shift, 1.0, 1.0);
-                    let color = Color32::from_rgb(r, g, b);
-                    ui.colored_label(color, format!("{:.0}°", hue_shift));
-                });
+// Gaps, corners, shadows, background and caption, which frame any layout
+fn show_style_settings(ui: &mut Ui, state: &mut AppState) {
+    let square_tiles = !matches!(state.collage_layout, CollageLayout::Hexagonal | CollageLayout::ColorWheel);
+    let style = &mut state.collage_style;
+    ui.horizontal(|ui| {
+        ui.label("Gap:").on_hover_text("Space between tiles and around the edge");
+        ui.add(egui::Slider::new(&mut style.gap, 0..=40).suffix(" px"));
+    });
+    if square_tiles {
+        ui.horizontal(|ui| {
+            ui.label("Rounded Corners:");
+            let mut percent = style.corner_radius * 100.0;
+            if ui.add(egui::Slider::new(&mut percent, 0.0..=50.0).suffix("%")).changed() {
+                style.corner_radius = percent / 100.0;
+            }
+        });
+    }
+    ui.checkbox(&mut style.shadow, "Drop Shadows");
+    ui.horizontal(|ui| {
+        ui.label("Background:").on_hover_text("Shows through gaps, padding and around shaped tiles");
+        ui.selectable_value(&mut style.background, CollageBackground::Transparent, "Transparent");
+        ui.selectable_value(&mut style.background, CollageBackground::Color, "Color");
+        ui.selectable_value(&mut style.background, CollageBackground::BlurredArt, "Blurred Artwork");
+        if style.background == CollageBackground::Color {
+            ui.color_edit_button_srgb(&mut style.background_color);
+        }
+    });
+    ui.checkbox(&mut style.fill_size, "Pad to Exact Size")
+        .on_hover_text("Centers the tiles on a collage of exactly the width and height above, rather than trimming the leftover space");
 
-                // Add options for gradient direction
-                ui.horizontal(|ui| {
-                    ui.label("Gradient Direction:");
-                    ui.selectable_value(&mut state.gradient_direction, GradientDirection::Diagonal, "Diagonal");
-                    ui.selectable_value(&mut state.gradient_direction, GradientDirection::Horizontal, "Horizontal");
-                    ui.selectable_value(&mut state.gradient_direction, GradientDirection::Vertical, "Vertical");
-                });
+    ui.horizontal(|ui| {
+        ui.label("Caption:");
+        ui.add(egui::TextEdit::singleline(&mut state.caption_title).hint_text("Title").desired_width(160.0));
+        ui.checkbox(&mut state.caption_username, "Username");
+        ui.checkbox(&mut state.caption_date_range, "Date Range")
+            .on_hover_text("The time ranges of top tracks and artists, or today's date for other sources");
+    });
+    if !caption_lines(state).is_empty() {
+        ui.horizontal(|ui| {
+            ui.label("Caption Position:");
+            let position = &mut state.collage_style.caption_position;
+            ui.selectable_value(position, CaptionPosition::Top, "Top");
+            ui.selectable_value(position, CaptionPosition::Bottom, "Bottom");
+        });
+    }
+}
 
-                // Conditionally show options for starting corner or side
-                match state.gradient_direction {
-                    GradientDirection::Diagonal => {
-                        ui.horizontal(|ui| {
-                            ui.label("Starting Corner:");
-                            ui.selectable_value(&mut state.starting_corner, StartingCorner::TopLeft, "Top Left");
-                            ui.selectable_value(&mut state.starting_corner, StartingCorner::TopRight, "Top Right");
-                            ui.selectable_value(&mut state.starting_corner, StartingCorner::BottomLeft, "Bottom Left");
-                            ui.selectable_value(&mut state.starting_corner, StartingCorner::BottomRight, "Bottom Right");
-                        });
-                    },
-                    GradientDirection::Horizontal => {
-                        ui.horizontal(|ui| {
-                            ui.label("Starting Side:");
-                            ui.selectable_value(&mut state.starting_corner, StartingCorner::TopLeft, "Top");
-                            ui.selectable_value(&mut state.starting_corner, StartingCorner::TopRight, "Bottom");
-                        });
-                    },
-                    GradientDirection::Vertical => {
-                        ui.horizontal(|ui| {
-                            ui.label("Starting Side:");
-                            ui.selectable_value(&mut state.starting_corner, StartingCorner::TopLeft, "Left");
-                            ui.selectable_value(&mut state.starting_corner, StartingCorner::BottomLeft, "Right");
+// The caption's lines from the title, username and date range options
+fn caption_lines(state: &AppState) -> Vec<String> {
+    let mut lines = Vec::new();
+    if !state.caption_title.trim().is_empty() {
+        lines.push(state.caption_title.trim().to_string());
+    }
+    if state.caption_username {
+        if let Some(name) = &state.username {
+            lines.push(name.clone());
+        }
+    }
+    if state.caption_date_range {
+        let mut ranges: Vec<&str> = Vec::new();
+        for source in &state.collage_sources {
+            if let CollageSource::TopTracks(range) | CollageSource::TopArtists(range) = source {
+                if !ranges.contains(&range.label()) {
+                    ranges.push(range.label());
+                }
+            }
+        }
+        if ranges.is_empty() {
+            lines.push(js_sys::Date::new_0().to_locale_date_string("default", &wasm_bindgen::JsValue::UNDEFINED).into());
+        } else {
+            // "Last 4 weeks and all time"
+            let joined = ranges.join(" and ");
+            let mut chars = joined.chars();
+            lines.push(chars.next().map(|first| first.to_uppercase().chain(chars).collect()).unwrap_or_default());
+        }
+    }
+    lines
+}
+
+// Format options and the download button. The image map files link each tile to Spotify
+fn show_export(ui: &mut Ui, image_data: &[u8]) {
+    let state = APP_STATE.lock().unwrap();
+    let mut format = state.export_format;
+    let mut jpeg_quality = state.jpeg_quality;
+    let mut image_map = state.export_image_map;
+    let details = state.collage_details.clone();
+    drop(state);
+
+    ui.horizontal(|ui| {
+        ui.label("Format:");
+        for option in ExportFormat::ALL {
+            ui.selectable_value(&mut format, option, option.label());
+        }
+        if format == ExportFormat::Jpeg {
+            ui.add(egui::Slider::new(&mut jpeg_quality, 10..=100).text("quality"));
+        }
+    });
+    ui.horizontal(|ui| {
+        if ui.button("Download Collage").on_hover_cursor(CursorIcon::PointingHand).clicked() {
+            download_collage(image_data, format, jpeg_quality, image_map, details.as_ref());
+        }
+        ui.add_enabled(details.is_some(), egui::Checkbox::new(&mut image_map, "Image Map"))
+            .on_hover_text("Also downloads an HTML page and JSON linking each tile to its album or artist on Spotify");
+    });
+
+    let mut state = APP_STATE.lock().unwrap();
+    state.export_format = format;
+    state.jpeg_quality = jpeg_quality;
+    state.export_image_map = image_map;
+}
+
+fn download_collage(png: &[u8], format: ExportFormat, jpeg_quality: u8, image_map: bool, details: Option<&CollageDetails>) {
+    let (width, height) = match image::ImageReader::new(std::io::Cursor::new(png)).with_guessed_format().map(|reader| reader.into_dimensions()) {
+        Ok(Ok(dimensions)) => dimensions,
+        _ => (0, 0),
+    };
+    let stem = details.map(|details| details.file_stem(width, height)).unwrap_or_else(|| "collage".to_string());
+    let image_file = format!("{}.{}", stem, format.extension());
+    match encode_collage(png, format, jpeg_quality, details) {
+        Ok(bytes) => download_file(&bytes, &image_file),
+        Err(e) => {
+            log_error(&format!("Failed to export collage: {}", e));
+            return;
+        }
+    }
+    if let Some(details) = details.filter(|_| image_map) {
+        download_file(details.image_map_html(&image_file, width, height).as_bytes(), &format!("{}-map.html", stem));
+        download_file(details.image_map_json(&image_file, width, height).as_bytes(), &format!("{}-map.json", stem));
+    }
+}
+
+// The photomosaic's picture, chosen from a file or a top artist's image, and how it's matched
+fn show_mosaic_settings(ui: &mut Ui, state: &mut AppState) {
+    ui.horizontal(|ui| {
+        ui.label("Picture:");
+        if ui.button("Choose Image…").on_hover_cursor(CursorIcon::PointingHand).clicked() {
+            spawn_local(async {
+                if let Some((name, bytes)) = pick_file("image/*").await {
+                    set_mosaic_target(name, bytes);
+                }
+            });
+        }
+
+        let artists = state.mosaic_artists.clone();
+        let loading = state.mosaic_artists_loading;
+        let menu = ui.menu_button("Artist Image", |ui| {
+            if artists.is_empty() {
+                ui.label(if loading { "Loading your top artists..." } else { "No top artists found" });
+            }
+            egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                for artist in &artists {
+                    if ui.button(&artist.name).clicked() {
+                        let (name, url) = (artist.name.clone(), artist.image_url.clone());
+                        spawn_local(async move {
+                            match reqwest::get(&url).await {
+                                Ok(response) => match response.bytes().await {
+                                    Ok(bytes) => set_mosaic_target(name, bytes.to_vec()),
+                                    Err(e) => log_error(&format!("Failed to read artist image: {:?}", e)),
+                                },
+                                Err(e) => log_error(&format!("Failed to download artist image: {:?}", e)),
+                            }
                         });
-                    },
+                        ui.close_menu();
+                    }
                 }
-                drop(state);
             });
+        });
+        // Top artists are only fetched once someone looks for them
+        if menu.inner.is_some() && artists.is_empty() && !loading {
+            state.mosaic_artists_loading = true;
+            spawn_local(async {
+                let token = get_token().unwrap_or_default();
+                let artists = fetch_top_artists(&token).await;
+                let mut state = APP_STATE.lock().unwrap();
+                state.mosaic_artists = artists;
+                state.mosaic_artists_loading = false;
+            });
+        }
+
+        match &state.mosaic_target {
+            Some((name, _)) => ui.label(name),
+            None => ui.weak("None chosen"),
+        };
+    });
+
+    let mosaic = &mut state.mosaic_settings;
+    ui.horizontal(|ui| {
+        ui.label("Columns:")
+            .on_hover_text("Cells across the picture; rows follow its proportions");
+        ui.add(egui::Slider::new(&mut mosaic.columns, 10..=150));
+    });
+    ui.horizontal(|ui| {
+        ui.label("Reuse Limit:")
+            .on_hover_text("Times a cover may be used before the next best match is picked. Once every cover is used up, the best matches are used again");
+        ui.add(egui::DragValue::new(&mut mosaic.reuse_limit).range(0..=100));
+        if mosaic.reuse_limit == 0 {
+            ui.weak("No limit");
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Tint:")
+            .on_hover_text("Blends each cover towards the color of its part of the picture");
+        let mut percent = mosaic.tint * 100.0;
+        if ui.add(egui::Slider::new(&mut percent, 0.0..=100.0).suffix("%")).changed() {
+            mosaic.tint = percent / 100.0;
+        }
+    });
+}
+
+// Keeps a chosen picture if it's an image format the collage can read
+fn set_mosaic_target(name: String, bytes: Vec<u8>) {
+    if image::guess_format(&bytes).is_err() {
+        log_error(&format!("{} isn't an image the photomosaic can use", name));
+        return;
+    }
+    APP_STATE.lock().unwrap().mosaic_target = Some((name, bytes));
+}
+
+// Names the sources in a few words, for the picker and the downloaded file
+fn sources_summary(sources: &[CollageSource], playlists: &[PlaylistInfo]) -> String {
+    match sources {
+        [] => "None".to_string(),
+        [CollageSource::Playlist(id)] => playlists
+            .iter()
+            .find(|p| &p.id == id)
+            .map(|p| p.name.clone())
+            .unwrap_or_else(|| "Playlist".to_string()),
+        [source] => source.label(),
+        _ => format!("{} sources", sources.len()),
+    }
+}
+
+// Chooses the sources the collage combines: the whole liked library, playlists, top tracks and
+// artists, and recent plays
+fn show_source_picker(ui: &mut Ui) {
+    let state = APP_STATE.lock().unwrap();
+    let mut sources = state.collage_sources.clone();
+    let playlists = state.playlists.clone();
+    drop(state);
+
+    let summary = sources_summary(&sources, &playlists);
+    let source_toggle = |ui: &mut Ui, sources: &mut Vec<CollageSource>, source: CollageSource, label: &str| {
+        let mut selected = sources.contains(&source);
+        if ui.checkbox(&mut selected, label).changed() {
+            if selected {
+                sources.push(source);
+            } else {
+                sources.retain(|s| s != &source);
+            }
+        }
+    };
 
-            // Show preview if we have a generated image
-            if let Some(image_data) = &collage_image {
-                if ui.button("Download Collage").on_hover_cursor(CursorIcon::PointingHand).clicked() {
-                    download_collage(image_data);
+    ui.horizontal(|ui| {
+        ui.label("Sources:");
+        ui.menu_button(summary, |ui| {
+            source_toggle(ui, &mut sources, CollageSource::LikedSongs, "Liked Songs");
+            source_toggle(ui, &mut sources, CollageSource::RecentlyPlayed, "Recently played");
+            ui.separator();
+            for range in TimeRange::ALL {
+                let source = CollageSource::TopTracks(range);
+                let label = source.label();
+                source_toggle(ui, &mut sources, source, &label);
+            }
+            for range in TimeRange::ALL {
+                let source = CollageSource::TopArtists(range);
+                let label = source.label();
+                source_toggle(ui, &mut sources, source, &label);
+            }
+            ui.separator();
+            ui.menu_button("Playlists", |ui| {
+                if playlists.is_empty() {
+                    ui.label("Your playlists haven't been loaded yet.");
+                    if ui.button("Load playlists").on_hover_cursor(CursorIcon::PointingHand).clicked() {
+                        let token = get_token().unwrap_or_default();
+                        spawn_local(async move {
+                            crate::api_request::playlists::fetch_playlists(token).await;
+                        });
+                    }
                 }
-                
-                // Convert image data to egui texture for preview
-                if let Ok(img) = image::load_from_memory(image_data) {
-                    let size = [img.width() as _, img.height() as _];
-                    let pixels = img.to_rgba8();
-                    let pixels = pixels.as_flat_samples();
-                    let color_image = ColorImage::from_rgba_unmultiplied(size, pixels.as_slice());
-                    let texture = ctx.load_texture(
-                        "collage-preview",
-                        color_image,
-                        Default::default()
-                    );
-                    
-                    // Calculate preview size to fit window while maintaining aspect ratio
-                    let max_width = ui.available_width().min(600.0);
-                    let aspect_ratio = size[0] as f32 / size[1] as f32;
-                    let preview_size = [max_width, max_width / aspect_ratio];
-                    
-                    ui.image(SizedTexture::new(texture.id(), preview_size));
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    for playlist in &playlists {
+                        source_toggle(ui, &mut sources, CollageSource::Playlist(playlist.id.clone()), &playlist.name);
+                    }
+                });
+            });
+        });
+    });
+
+    APP_STATE.lock().unwrap().collage_sources = sources;
+}
+
+// Uploads the generated collage as the cover of a playlist the user can edit
+fn show_cover_upload(ui: &mut Ui, image_data: &[u8]) {
+    let state = APP_STATE.lock().unwrap();
+    let user_id = state.user_id.clone().unwrap_or_default();
+    let playlists: Vec<_> = state.playlists.iter().filter(|p| p.is_editable_by(&user_id)).cloned().collect();
+    let mut target = state.cover_target.clone().filter(|id| playlists.iter().any(|p| &p.id == id));
+    let uploading = state.cover_uploading;
+    let loading = state.collage_loading;
+    drop(state);
+
+    if playlists.is_empty() {
+        return;
+    }
+
+    // Covers are square, and cropping would cut off the collage's edges
+    let square = image::ImageReader::new(std::io::Cursor::new(image_data))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_dimensions().ok())
+        .is_some_and(|(width, height)| width == height);
+
+    ui.horizontal(|ui| {
+        ui.label("Playlist cover:");
+        let selected_text = target
+            .as_ref()
+            .and_then(|id| playlists.iter().find(|p| &p.id == id))
+            .map(|p| p.name.clone())
+            .unwrap_or_else(|| "Choose a playlist".to_string());
+        egui::ComboBox::from_id_salt("cover_target")
+            .selected_text(selected_text)
+            .show_ui(ui, |ui| {
+                for playlist in &playlists {
+                    ui.selectable_value(&mut target, Some(playlist.id.clone()), &playlist.name);
                 }
+            });
+
+        if !square {
+            let generate = ui.add_enabled(!loading, egui::Button::new("Generate Square Collage"))
+                .on_hover_text("Playlist covers are square, so this one can't be uploaded as it is")
+                .on_hover_cursor(CursorIcon::PointingHand);
+            if generate.clicked() {
+                start_collage_generation(true);
             }
-            
-            // Only show generate button when not loading
-            if !collage_loading {
-                if ui.button("Generate New Collage").on_hover_cursor(CursorIcon::PointingHand).clicked() {
-                    let mut state = APP_STATE.lock().unwrap();
-                    let tracks = state.saved_tracks.clone();
-                    let width = state.collage_width;
-                    let height = state.collage_height;
-                    let hue_shift = state.hue_shift; // Get hue shift value from state
-                    let gradient_direction = state.gradient_direction;
-                    let starting_corner = state.starting_corner;
-                    
-                    // Set collage_loading to true
-                    state.collage_loading = true;
-                    
+        }
+        let upload = ui.add_enabled(square && target.is_some() && !uploading, egui::Button::new("Upload as Cover"))
+            .on_hover_text("The collage becomes the playlist's cover")
+            .on_disabled_hover_text("Only a square collage can become a playlist's cover")
+            .on_hover_cursor(CursorIcon::PointingHand);
+        if uploading {
+            ui.spinner();
+        }
+        if let Some(playlist_id) = target.clone().filter(|_| upload.clicked()) {
+            let cover = image::load_from_memory(image_data).ok().and_then(|image| encode_playlist_cover(&image));
+            match cover {
+                Some(cover) => {
+                    let token = get_token().unwrap_or_default();
                     spawn_local(async move {
-                        // Download and process album artwork
-                        let mut images = Vec::new();
-                        let total_images = tracks.len();
-                        let mut loaded_count = 0;
-                        
-                        // Update loading message
-                        {
-                            let mut state = APP_STATE.lock().unwrap();
-                            state.loading_message = format!("Loading images (0/{})...", total_images);
-                        }
-                        
-                        for (_, _, image_url, _) in tracks {
-                            if let Ok(bytes) = reqwest::get(&image_url).await {
-                                if let Ok(bytes) = bytes.bytes().await {
-                                    if let Ok(img) = image::load_from_memory(&bytes) {
-                                        images.push(img);
-                                        loaded_count += 1;
-                                        // Update progress
-                                        let mut state = APP_STATE.lock().unwrap();
-                                        state.progress = loaded_count as f32 / total_images as f32;
-                                    }
-                                }
-                            }
-                        }
-                        
-                        // Only proceed if we have images
-                        if images.is_empty() {
-                            let mut state = APP_STATE.lock().unwrap();
-                            state.progress = 0.0;
-                            state.collage_loading = false; // Reset collage_loading
-                            return;
-                        }
-                        
-                        // Create collage with downloaded images
-                        if let Ok(collage) = create_collage(images, width, height, hue_shift, gradient_direction, starting_corner) {
-                            // Create a cursor to write the image to
-                            let mut cursor = Cursor::new(Vec::new());
-                            if let Ok(_) = collage.write_to(&mut cursor, image::ImageFormat::Png) {
-                                let buffer = cursor.into_inner();
-                                let mut state = APP_STATE.lock().unwrap();
-                                state.collage_image = Some(buffer);
-                            }
-                        }
-                        
-                        // Update loading state
-                        let mut state = APP_STATE.lock().unwrap();
-                        state.progress = 0.0;
-                        state.collage_loading = false; // Reset collage_loading
+                        crate::api_request::playlist_edit::upload_playlist_cover(token, playlist_id, cover).await;
                     });
                 }
+                None => log_error("Failed to encode the collage as a playlist cover"),
             }
-            
-            if collage_loading {
-                let progress_text = format!("{}/{}", (progress * saved_tracks.len() as f32).round() as i32, saved_tracks.len());
-                ui.add(ProgressBar::new(progress).animate(true).text(progress_text));
-            }
-        });
+        }
+    });
 
-    let mut state = APP_STATE.lock().unwrap();
-    state.collage_window_open = collage_window_open;
-    if let Some(resp) = window {
-        let r = resp.response.rect;
-        state.collage_window_pos = (r.min.x, r.min.y);
-    }
+    APP_STATE.lock().unwrap().cover_target = target;
 }
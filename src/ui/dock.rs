@@ -0,0 +1,211 @@
+use egui::{Id, Ui, WidgetText};
+use egui_dock::{DockArea, DockState, Style, TabViewer};
+use serde::{Deserialize, Serialize};
+use super::app_state::{AppState, APP_STATE};
+use super::savedtracks::show_saved_tracks_contents;
+use super::playlists_window::show_playlists_contents;
+use super::search_window::show_search_contents;
+use super::playlist_tracks::show_playlist_tracks_contents;
+use super::collage::show_collage_contents;
+use super::settings::show_settings_contents;
+use crate::mediaplayer::mediaplayerwidget::show_mediaplayer_contents;
+use crate::storage::{load_dock_layout, save_dock_layout};
+use crate::utils::log_error;
+
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DockTab {
+    LikedSongs,
+    Playlists,
+    Playlist(String), // Spotify playlist ID
+    Search,
+    Player,
+    Collage,
+    Settings,
+}
+
+#[derive(Default)]
+struct DockTabViewer {
+    reset_triggered: bool, // Set by the settings tab's reset buttons
+}
+
+impl TabViewer for DockTabViewer {
+    type Tab = DockTab;
+
+    fn title(&mut self, tab: &mut DockTab) -> WidgetText {
+        match tab {
+            DockTab::LikedSongs => "Liked Songs".into(),
+            DockTab::Playlists => "Your Playlists".into(),
+            DockTab::Playlist(id) => {
+                let state = APP_STATE.lock().unwrap();
+                state.playlist_windows
+                    .iter()
+                    .find(|w| &w.id == id)
+                    .map(|w| w.name.clone())
+                    .unwrap_or_else(|| "Playlist".to_string())
+                    .into()
+            }
+            DockTab::Search => "Search".into(),
+            DockTab::Player => "Music Player".into(),
+            DockTab::Collage => "Collage".into(),
+            DockTab::Settings => "Settings".into(),
+        }
+    }
+
+    fn ui(&mut self, ui: &mut Ui, tab: &mut DockTab) {
+        match tab {
+            DockTab::LikedSongs => show_saved_tracks_contents(ui),
+            DockTab::Playlists => show_playlists_contents(ui),
+            DockTab::Playlist(id) => show_playlist_tracks_contents(ui, id),
+            DockTab::Search => show_search_contents(ui),
+            DockTab::Player => show_mediaplayer_contents(ui),
+            DockTab::Collage => show_collage_contents(ui),
+            DockTab::Settings => {
+                let mut state = APP_STATE.lock().unwrap();
+                show_settings_contents(ui, &mut state, &mut self.reset_triggered);
+            }
+        }
+    }
+
+    // Playlist names aren't unique, so tabs are identified by their value rather than their title
+    fn id(&mut self, tab: &mut DockTab) -> Id {
+        Id::new(("dock_tab", &*tab))
+    }
+
+    fn on_close(&mut self, tab: &mut DockTab) -> bool {
+        let mut state = APP_STATE.lock().unwrap();
+        match tab {
+            DockTab::LikedSongs => state.tracks_window_open = false,
+            DockTab::Playlists => state.playlists_window_open = false,
+            DockTab::Playlist(id) => state.playlist_windows.retain(|w| &w.id != id),
+            DockTab::Search => state.search_window_open = false,
+            DockTab::Player => state.player_window_open = false,
+            DockTab::Collage => state.collage_window_open = false,
+            DockTab::Settings => {
+                state.settings_window_open = false;
+                state.settings_initialized = false;
+            }
+        }
+        true
+    }
+
+    // The track views bring their own scroll areas; only the long settings page needs one from the dock
+    fn scroll_bars(&self, tab: &DockTab) -> [bool; 2] {
+        [false, *tab == DockTab::Settings]
+    }
+}
+
+// Tabs that should be docked, derived from the same open flags the floating windows use
+fn open_tabs(state: &AppState) -> Vec<DockTab> {
+    let mut tabs = Vec::new();
+    if state.show_tracks && state.tracks_window_open {
+        tabs.push(DockTab::LikedSongs);
+    }
+    if state.show_playlists && state.playlists_window_open {
+        tabs.push(DockTab::Playlists);
+    }
+    for playlist_window in &state.playlist_windows {
+        if playlist_window.open {
+            tabs.push(DockTab::Playlist(playlist_window.id.clone()));
+        }
+    }
+    if state.search_window_open {
+        tabs.push(DockTab::Search);
+    }
+    if state.player_window_open {
+        tabs.push(DockTab::Player);
+    }
+    if state.collage_window_open {
+        tabs.push(DockTab::Collage);
+    }
+    if state.settings_window_open {
+        tabs.push(DockTab::Settings);
+    }
+    tabs
+}
+
+pub struct DockWorkspace {
+    pub state: DockState<DockTab>,
+    last_saved_layout: String,
+}
+
+impl Default for DockWorkspace {
+    fn default() -> Self {
+        Self {
+            state: DockState::new(Vec::new()),
+            last_saved_layout: String::new(),
+        }
+    }
+}
+
+impl DockWorkspace {
+    // Loads the saved tree; the windows its tabs refer to are reopened by the session restore
+    pub fn restore() -> Self {
+        match load_dock_layout() {
+            Some(dock_state) => Self {
+                last_saved_layout: serde_json::to_string(&dock_state).unwrap_or_default(),
+                state: dock_state,
+            },
+            None => Self::default(),
+        }
+    }
+
+    // Adds tabs for windows opened elsewhere (sidebar, playlist rows) and drops ones closed elsewhere
+    fn sync_tabs(&mut self) {
+        let open = {
+            let state = APP_STATE.lock().unwrap();
+            open_tabs(&state)
+        };
+
+        let stale: Vec<DockTab> = self.state
+            .iter_all_tabs()
+            .map(|(_, tab)| tab.clone())
+            .filter(|tab| !open.contains(tab))
+            .collect();
+        for tab in stale {
+            if let Some(index) = self.state.find_tab(&tab) {
+                self.state.remove_tab(index);
+            }
+        }
+
+        for tab in open {
+            if self.state.find_tab(&tab).is_none() {
+                self.state.push_to_focused_leaf(tab);
+            }
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut Ui) {
+        self.sync_tabs();
+
+        if self.state.iter_all_tabs().next().is_none() {
+            ui.vertical_centered(|ui| {
+                ui.add_space(100.0);
+                ui.label("Open your liked songs, playlists or the player from the sidebar to dock them here.");
+            });
+        } else {
+            let mut viewer = DockTabViewer::default();
+            DockArea::new(&mut self.state)
+                .id(Id::new("workspace_dock"))
+                .style(Style::from_egui(ui.style().as_ref()))
+                .show_inside(ui, &mut viewer);
+            // Resetting window positions puts every open tab back into a single leaf
+            if viewer.reset_triggered {
+                self.state = DockState::new(Vec::new());
+                self.sync_tabs();
+            }
+        }
+
+        self.save_if_changed();
+    }
+
+    fn save_if_changed(&mut self) {
+        if let Ok(layout) = serde_json::to_string(&self.state) {
+            if layout != self.last_saved_layout {
+                if let Err(e) = save_dock_layout(&layout) {
+                    log_error(&format!("Failed to save dock layout: {}", e));
+                }
+                self.last_saved_layout = layout;
+            }
+        }
+    }
+}
@@ -0,0 +1,301 @@
+use egui::{CursorIcon, Ui};
+use egui_dock::DockState;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen_futures::spawn_local;
+use web_sys::window;
+use super::app_state::{AppState, PlaylistWindow, TrackList, ViewMode};
+use super::dock::{DockTab, DockWorkspace};
+use crate::api_request::token::get_token;
+use crate::storage::{load_playlist_tracks, save_layout_presets};
+use crate::utils::{download_file, log_error};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PresetPlaylistWindow {
+    pub id: String, // Spotify playlist ID
+    pub name: String,
+    pub view_mode: ViewMode,
+    pub pos: (f32, f32),
+    pub size: (f32, f32),
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LayoutPreset {
+    pub name: String,
+    pub liked_songs_open: bool,
+    pub playlists_open: bool,
+    pub player_open: bool,
+    pub collage_open: bool,
+    pub settings_open: bool,
+    #[serde(default)] // Missing from presets saved before catalog search existed
+    pub search_open: bool,
+    pub liked_songs_window_pos: (f32, f32),
+    pub liked_songs_window_size: (f32, f32),
+    pub playlists_window_pos: (f32, f32),
+    pub playlists_window_size: (f32, f32),
+    pub music_player_window_pos: (f32, f32),
+    pub music_player_window_size: (f32, f32),
+    pub collage_window_pos: (f32, f32),
+    pub collage_window_size: (f32, f32),
+    pub settings_window_pos: (f32, f32),
+    pub view_mode: ViewMode,
+    pub playlist_view_mode: ViewMode,
+    pub playlist_windows: Vec<PresetPlaylistWindow>,
+    pub dock_mode: bool,
+    pub dock_layout: Option<DockState<DockTab>>,
+}
+
+impl LayoutPreset {
+    pub fn capture(name: String, state: &AppState, dock: &DockWorkspace) -> Self {
+        LayoutPreset {
+            name,
+            liked_songs_open: state.show_tracks && state.tracks_window_open,
+            playlists_open: state.show_playlists && state.playlists_window_open,
+            player_open: state.player_window_open,
+            collage_open: state.collage_window_open,
+            settings_open: state.settings_window_open,
+            search_open: state.search_window_open,
+            liked_songs_window_pos: state.liked_songs_window_pos,
+            liked_songs_window_size: state.tracks_window_size,
+            playlists_window_pos: state.playlists_window_pos,
+            playlists_window_size: state.playlists_window_size,
+            music_player_window_pos: state.music_player_window_pos,
+            music_player_window_size: state.music_player_window_size,
+            collage_window_pos: state.collage_window_pos,
+            collage_window_size: state.collage_window_size,
+            settings_window_pos: state.settings_window_pos,
+            view_mode: state.view_mode,
+            playlist_view_mode: state.playlist_view_mode,
+            playlist_windows: state.playlist_windows
+                .iter()
+                .filter(|w| w.open)
+                .map(|w| PresetPlaylistWindow {
+                    id: w.id.clone(),
+                    name: w.name.clone(),
+                    view_mode: w.view_mode,
+                    pos: w.pos,
+                    size: w.size,
+                })
+                .collect(),
+            dock_mode: state.dock_mode,
+            dock_layout: if state.dock_mode { Some(dock.state.clone()) } else { None },
+        }
+    }
+
+    pub fn apply(&self, state: &mut AppState, dock: &mut DockWorkspace) {
+        let token = get_token().unwrap_or_default();
+
+        state.liked_songs_window_pos = self.liked_songs_window_pos;
+        state.tracks_window_size = self.liked_songs_window_size;
+        state.playlists_window_pos = self.playlists_window_pos;
+        state.playlists_window_size = self.playlists_window_size;
+        state.music_player_window_pos = self.music_player_window_pos;
+        state.music_player_window_size = self.music_player_window_size;
+        state.collage_window_pos = self.collage_window_pos;
+        state.collage_window_size = self.collage_window_size;
+        state.settings_window_pos = self.settings_window_pos;
+        state.view_mode = self.view_mode;
+        state.playlist_view_mode = self.playlist_view_mode;
+        // Window sizes only apply to new windows, so recreate them under fresh IDs
+        state.layout_generation += 1;
+
+        state.tracks_window_open = false;
+        if self.liked_songs_open {
+            reopen_liked_songs(state, &token);
+        }
+        state.playlists_window_open = false;
+        if self.playlists_open {
+            reopen_playlists(state, &token);
+        }
+        state.player_window_open = self.player_open;
+        state.collage_window_open = self.collage_open;
+        state.settings_window_open = self.settings_open;
+        state.search_window_open = self.search_open;
+
+        state.playlist_windows.retain(|w| self.playlist_windows.iter().any(|p| p.id == w.id));
+        for preset_window in &self.playlist_windows {
+            let mut playlist_window = PlaylistWindow::new(preset_window.id.clone(), preset_window.name.clone());
+            playlist_window.view_mode = preset_window.view_mode;
+            playlist_window.pos = preset_window.pos;
+            playlist_window.size = preset_window.size;
+            reopen_playlist_window(state, &token, playlist_window);
+        }
+
+        if state.dock_mode != self.dock_mode {
+            state.dock_mode = self.dock_mode;
+            if let Some(window) = window() {
+                if let Ok(Some(storage)) = window.local_storage() {
+                    let _ = storage.set_item("dock_mode", &state.dock_mode.to_string());
+                }
+            }
+        }
+        if let Some(dock_layout) = &self.dock_layout {
+            dock.state = dock_layout.clone();
+        }
+    }
+}
+
+// Reopens Liked Songs, fetching the library if it hasn't been loaded yet
+pub fn reopen_liked_songs(state: &mut AppState, token: &str) {
+    state.show_tracks = true;
+    state.tracks_window_open = true;
+    if state.saved_tracks.is_empty() && !state.is_loading {
+        let token = token.to_string();
+        spawn_local(async move {
+            crate::api_request::saved_tracks::fetch_saved_tracks(token).await;
+        });
+    }
+}
+
+// Reopens the playlist browser, fetching the playlists if they haven't been loaded yet
+pub fn reopen_playlists(state: &mut AppState, token: &str) {
+    state.show_playlists = true;
+    state.playlists_window_open = true;
+    if state.playlists.is_empty() {
+        let token = token.to_string();
+        spawn_local(async move {
+            crate::api_request::playlists::fetch_playlists(token).await;
+        });
+    }
+}
+
+// Reopens a playlist window with the given placement; if it isn't open yet its tracks come from cache or the API
+pub fn reopen_playlist_window(state: &mut AppState, token: &str, mut playlist_window: PlaylistWindow) {
+    if let Some(existing) = state.playlist_windows.iter_mut().find(|w| w.id == playlist_window.id) {
+        existing.view_mode = playlist_window.view_mode;
+        existing.pos = playlist_window.pos;
+        existing.size = playlist_window.size;
+        existing.open = true;
+        return;
+    }
+
+    if let Some(stored_playlist) = load_playlist_tracks(&playlist_window.id) {
+        playlist_window.name = stored_playlist.name;
+        playlist_window.tracks = TrackList::with_positions(stored_playlist.tracks, stored_playlist.positions);
+        playlist_window.complete = stored_playlist.complete;
+        playlist_window.snapshot_id = stored_playlist.snapshot_id;
+        state.playlist_windows.push(playlist_window);
+        return;
+    }
+
+    let id = playlist_window.id.clone();
+    state.playlist_windows.push(playlist_window);
+    let token = token.to_string();
+    spawn_local(async move {
+        crate::api_request::playlist_tracks::fetch_playlist_tracks(id, token).await;
+    });
+}
+
+// Accepts either a single exported preset or a list of them
+fn parse_presets(json: &str) -> Result<Vec<LayoutPreset>, String> {
+    serde_json::from_str::<Vec<LayoutPreset>>(json)
+        .or_else(|_| serde_json::from_str::<LayoutPreset>(json).map(|preset| vec![preset]))
+        .map_err(|e| format!("Invalid layout JSON: {}", e))
+}
+
+// Adds a preset, replacing any existing preset with the same name
+fn upsert_preset(presets: &mut Vec<LayoutPreset>, preset: LayoutPreset) {
+    if let Some(existing) = presets.iter_mut().find(|p| p.name == preset.name) {
+        *existing = preset;
+    } else {
+        presets.push(preset);
+    }
+}
+
+fn persist_presets(presets: &[LayoutPreset]) {
+    if let Err(e) = save_layout_presets(presets) {
+        log_error(&format!("Failed to save layout presets: {}", e));
+    }
+}
+
+// Sidebar section for saving, switching and sharing layout presets
+pub fn show_layouts_section(ui: &mut Ui, state: &mut AppState, dock: &mut DockWorkspace) {
+    ui.collapsing("Layouts", |ui| {
+        let mut apply_index = None;
+        let mut delete_index = None;
+        for (index, preset) in state.layout_presets.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.button(&preset.name)
+                    .on_hover_text("Switch to this layout")
+                    .on_hover_cursor(CursorIcon::PointingHand)
+                    .clicked() {
+                    apply_index = Some(index);
+                }
+                if ui.small_button(egui_phosphor::bold::TRASH)
+                    .on_hover_text("Delete layout")
+                    .on_hover_cursor(CursorIcon::PointingHand)
+                    .clicked() {
+                    delete_index = Some(index);
+                }
+            });
+        }
+
+        if let Some(index) = apply_index {
+            let preset = state.layout_presets[index].clone();
+            preset.apply(state, dock);
+        }
+        if let Some(index) = delete_index {
+            state.layout_presets.remove(index);
+            persist_presets(&state.layout_presets);
+        }
+
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut state.new_layout_name)
+                    .hint_text("Layout name")
+                    .desired_width(100.0)
+            );
+            let name = state.new_layout_name.trim().to_string();
+            if ui.add_enabled(!name.is_empty(), egui::Button::new("Save"))
+                .on_hover_text("Save the current windows as a layout, replacing one with the same name")
+                .on_hover_cursor(CursorIcon::PointingHand)
+                .clicked() {
+                let preset = LayoutPreset::capture(name, state, dock);
+                upsert_preset(&mut state.layout_presets, preset);
+                persist_presets(&state.layout_presets);
+                state.new_layout_name.clear();
+            }
+        });
+
+        ui.menu_button("Import / Export", |ui| {
+            ui.set_min_width(220.0);
+            let exported = serde_json::to_string_pretty(&state.layout_presets).unwrap_or_default();
+            if ui.button("Copy layouts as JSON").on_hover_cursor(CursorIcon::PointingHand).clicked() {
+                ui.ctx().copy_text(exported.clone());
+                ui.close_menu();
+            }
+            if ui.button("Download layouts.json").on_hover_cursor(CursorIcon::PointingHand).clicked() {
+                download_file(exported.as_bytes(), "layouts.json");
+                ui.close_menu();
+            }
+            ui.separator();
+            ui.label("Paste shared layouts:");
+            let edited = ui.add(
+                egui::TextEdit::multiline(&mut state.layout_import_text)
+                    .desired_rows(4)
+                    .desired_width(f32::INFINITY)
+            );
+            if edited.changed() {
+                state.layout_import_error = None;
+            }
+            if ui.add_enabled(!state.layout_import_text.trim().is_empty(), egui::Button::new("Import"))
+                .on_hover_cursor(CursorIcon::PointingHand)
+                .clicked() {
+                match parse_presets(&state.layout_import_text) {
+                    Ok(presets) => {
+                        for preset in presets {
+                            upsert_preset(&mut state.layout_presets, preset);
+                        }
+                        persist_presets(&state.layout_presets);
+                        state.layout_import_text.clear();
+                        state.layout_import_error = None;
+                        ui.close_menu();
+                    }
+                    Err(e) => state.layout_import_error = Some(e),
+                }
+            }
+            if let Some(error) = &state.layout_import_error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+        });
+    });
+}
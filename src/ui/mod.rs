@@ -3,8 +3,15 @@ mod savedtracks;
 mod ui;
 mod settings;
 mod collage;
-mod tracks_ui;
+pub mod tracks_ui;
 mod playlists_window;
+mod search_window;
+pub mod dock;
+pub mod layouts;
+pub mod session;
+pub mod track_table;
+pub mod track_query;
+pub mod track_selection;
 pub mod playlist_tracks;  // Make this public
 
 pub use app_state::*;
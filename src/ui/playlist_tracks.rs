@@ -1,90 +1,139 @@
-use egui::Context;
-use crate::ui::{APP_STATE, tracks_ui::{show_list_view, show_grid_view, ListViewMode}};
-use crate::ui::app_state::ViewMode;
+use egui::{Context, Ui};
+use crate::ui::{APP_STATE, tracks_ui::{accept_playlist_drop, show_list_view, show_grid_view, ListContext, ListViewMode}};
+use crate::ui::app_state::{window_content_size, ViewMode};
+use crate::ui::track_table::show_table_view;
+use crate::ui::track_query::{filter_tracks, show_search_box};
+use crate::ui::track_selection::show_selection_bar;
 use egui::CursorIcon;
 
 pub fn show_playlist_tracks_windows(ctx: &Context) {
     let state = APP_STATE.lock().unwrap();
     let playlist_windows = state.playlist_windows.clone();
-    let user_id = state.user_id.clone().unwrap_or_default();
+    let constrain_rect = state.constrain_to_central_panel(ctx);
+    let layout_generation = state.layout_generation;
     drop(state); // Release lock to avoid conflicts
 
-    for (playlist_id, playlist_name, tracks, mut view_mode, window_open, window_pos) in playlist_windows {
-        let mut local_window_open = window_open;
-        let mut state = APP_STATE.lock().unwrap();
-        let constrain_rect = state.constrain_to_central_panel(ctx);
-        drop(state);
+    for playlist_window in playlist_windows {
+        let playlist_id = playlist_window.id;
+        let mut local_window_open = playlist_window.open;
 
-        let window = egui::Window::new(&playlist_name)
+        let window = egui::Window::new(&playlist_window.name)
+            .id(egui::Id::new(("playlist_window", &playlist_id, layout_generation)))
             .open(&mut local_window_open)
-            .current_pos(window_pos)
-            .default_size((600.0, 400.0))
+            .current_pos(playlist_window.pos)
+            .default_size(window_content_size(ctx, playlist_window.size))
             .constrain_to(constrain_rect)
             .show(ctx, |ui| {
-                // Add view mode controls
-                ui.horizontal(|ui| {
-                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        if ui.toggle_value(&mut (view_mode == ViewMode::List), &format!("{} List", egui_phosphor::bold::LIST)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
-                            let mut state = APP_STATE.lock().unwrap();
-                            if let Some(window_state) = state.playlist_windows.iter_mut().find(|w| w.0 == playlist_id) {
-                                window_state.3 = ViewMode::List;
-                            }
-                            view_mode = ViewMode::List;
-                        }
-                        ui.add_space(8.0);
-                        if ui.toggle_value(&mut (view_mode == ViewMode::Grid), &format!("{} Grid", egui_phosphor::bold::SQUARES_FOUR)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
-                            let mut state = APP_STATE.lock().unwrap();
-                            if let Some(window_state) = state.playlist_windows.iter_mut().find(|w| w.0 == playlist_id) {
-                                window_state.3 = ViewMode::Grid;
-                            }
-                            view_mode = ViewMode::Grid;
-                        }
-                        ui.label("View:");
-                    });
-                });
-                ui.add_space(8.0);
-
-                egui::ScrollArea::vertical().show(ui, |ui| {
-                    match view_mode {
-                        ViewMode::List => {
-                            let enumerated: Vec<_> = tracks.iter().enumerate().collect();
-                            show_list_view(
-                                ui,
-                                &enumerated,
-                                ListViewMode::Tracks,
-                                Some(&playlist_id),
-                                &user_id
-                            );
-                        },
-                        ViewMode::Grid => {
-                            let enumerated: Vec<_> = tracks.iter().enumerate().collect();
-                            show_grid_view(
-                                ui,
-                                &enumerated,
-                                None,
-                                tracks.len(),
-                                tracks.len() as i32,
-                                ListViewMode::Tracks,
-                                Some(&playlist_id),
-                                &user_id
-                            );
-                        }
-                    }
-                });
+                show_playlist_tracks_contents(ui, &playlist_id);
             });
 
         let mut state = APP_STATE.lock().unwrap();
-        if let Some(resp) = window {
-            let r = resp.response.rect;
-            if let Some(window_state) = state.playlist_windows.iter_mut().find(|w| w.0 == playlist_id) {
-                window_state.5 = (r.min.x, r.min.y);
-                window_state.4 = local_window_open;
-                window_state.3 = view_mode;
+        if let Some(window_state) = state.playlist_windows.iter_mut().find(|w| w.id == playlist_id) {
+            window_state.open = local_window_open;
+            if let Some(resp) = window {
+                let r = resp.response.rect;
+                window_state.pos = (r.min.x, r.min.y);
+                if resp.inner.is_some() {
+                    window_state.size = (r.width(), r.height());
+                }
             }
         }
     }
 
     // Clean up closed windows
     let mut state = APP_STATE.lock().unwrap();
-    state.playlist_windows.retain(|(_, _, _, _, w_open, _)| *w_open);
+    state.playlist_windows.retain(|w| w.open);
+}
+
+// Renders one playlist's tracks, shared by the floating window and the dock tab
+pub fn show_playlist_tracks_contents(ui: &mut Ui, playlist_id: &str) {
+    let mut state = APP_STATE.lock().unwrap();
+    let window_state = state.playlist_windows.iter().find(|w| w.id == playlist_id).cloned();
+    let user_id = state.user_id.clone().unwrap_or_default();
+    let scroll_key = format!("playlist:{}", playlist_id);
+    let has_tracks = window_state.as_ref().is_some_and(|w| !w.tracks.is_empty());
+    let scroll_offset = state.take_pending_scroll_offset(&scroll_key, has_tracks);
+    let mut selection = state.track_selections.get(&scroll_key).cloned().unwrap_or_default();
+    drop(state);
+
+    let (tracks, complete, mut view_mode, mut search_text) = match window_state {
+        Some(window_state) => (window_state.tracks, window_state.complete, window_state.view_mode, window_state.search_text),
+        None => {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Loading playlist...");
+            });
+            return;
+        }
+    };
+
+    // Add search and view mode controls
+    ui.horizontal(|ui| {
+        ui.horizontal(|ui| {
+            show_search_box(ui, &mut search_text);
+        });
+
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if ui.toggle_value(&mut (view_mode == ViewMode::Table), format!("{} Table", egui_phosphor::bold::TABLE)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+                view_mode = ViewMode::Table;
+            }
+            ui.add_space(8.0);
+            if ui.toggle_value(&mut (view_mode == ViewMode::List), format!("{} List", egui_phosphor::bold::LIST)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+                view_mode = ViewMode::List;
+            }
+            ui.add_space(8.0);
+            if ui.toggle_value(&mut (view_mode == ViewMode::Grid), format!("{} Grid", egui_phosphor::bold::SQUARES_FOUR)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+                view_mode = ViewMode::Grid;
+            }
+            ui.label("View:");
+        });
+    });
+    ui.add_space(8.0);
+    show_selection_bar(ui, &mut selection, &tracks, Some(playlist_id), false);
+
+    let enumerated = filter_tracks(ui, &scroll_key, &search_text, &tracks);
+    let mut list = ListContext {
+        mode: ListViewMode::Tracks,
+        playlist_id: Some(playlist_id),
+        user_id: &user_id,
+        // Positions past the loaded items aren't known, so only whole playlists can be reordered
+        reorderable: complete && search_text.trim().is_empty(),
+        selection: &mut selection,
+    };
+    let offset = match view_mode {
+        ViewMode::List => show_list_view(
+            ui,
+            &enumerated,
+            &mut list,
+            scroll_offset,
+            None
+        ),
+        ViewMode::Table => show_table_view(
+            ui,
+            &enumerated,
+            &scroll_key,
+            (tracks.generation(), &search_text),
+            Some(playlist_id),
+            &user_id,
+            scroll_offset
+        ),
+        ViewMode::Grid => show_grid_view(
+            ui,
+            &enumerated,
+            None,
+            tracks.len(),
+            tracks.len() as i32,
+            &mut list,
+            scroll_offset
+        ),
+    };
+    accept_playlist_drop(ui, playlist_id);
+
+    let mut state = APP_STATE.lock().unwrap();
+    state.track_selections.insert(scroll_key.clone(), selection);
+    state.scroll_offsets.insert(scroll_key, offset);
+    if let Some(window_state) = state.playlist_windows.iter_mut().find(|w| w.id == playlist_id) {
+        window_state.view_mode = view_mode;
+        window_state.search_text = search_text;
+    }
 }
@@ -1,122 +1,243 @@
-use egui::Context;
-use crate::ui::app_state::{APP_STATE, ViewMode};
-use crate::ui::tracks_ui::{show_grid_view, ListViewMode, render_square_with_image};
+use egui::{Context, Ui};
+use crate::ui::app_state::{window_content_size, APP_STATE, PlaylistDraft, ViewMode};
+use crate::api_request::models::PlaylistInfo;
+use crate::ui::tracks_ui::{show_context_menu, show_grid_view, ListContext, ListViewMode, render_square_with_image};
+use crate::ui::track_selection::TrackSelection;
 use egui::CursorIcon;
+use crate::api_request::models::TrackInfo;
 
 pub fn show_playlists_window(ctx: &Context) {
-    let mut state = APP_STATE.lock().unwrap();
+    let state = APP_STATE.lock().unwrap();
     if !state.show_playlists {
         return;
     }
 
     let mut playlists_window_open = state.playlists_window_open;
     let window_pos = state.playlists_window_pos;
-    let mut window_size = state.playlists_window_size; // Make mutable
+    let window_size = state.playlists_window_size;
     let constrain_rect = state.constrain_to_central_panel(ctx);
-    let playlists = state.playlists.clone();
-    let user_id = state.user_id.clone().unwrap_or_default();
-    let mut view_mode = state.playlist_view_mode;
+    let layout_generation = state.layout_generation;
     drop(state);
 
     let window = egui::Window::new("Your Playlists")
+        .id(egui::Id::new(("playlists_window", layout_generation)))
         .open(&mut playlists_window_open)
         .current_pos([window_pos.0, window_pos.1])
-        .default_size(window_size)
+        .default_size(window_content_size(ctx, window_size))
         .constrain_to(constrain_rect)
         .show(ctx, |ui| {
-            // Add view mode controls
-            ui.horizontal(|ui| {
-                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    if ui.toggle_value(&mut (view_mode == ViewMode::List), &format!("{} List", egui_phosphor::bold::LIST)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
-                        view_mode = ViewMode::List;
-                        window_size = (400.0, 600.0); // Allowed since mutable
-                    }
-                    ui.add_space(8.0);
-                    if ui.toggle_value(&mut (view_mode == ViewMode::Grid), &format!("{} Grid", egui_phosphor::bold::SQUARES_FOUR)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
-                        window_size = (800.0, 600.0); // Allowed since mutable
-                        view_mode = ViewMode::Grid;
-                    }
-                    ui.label("View:");
-                });
-            });
+            show_playlists_contents(ui);
+        });
+
+    let mut state = APP_STATE.lock().unwrap();
+    state.playlists_window_open = playlists_window_open;
+    if let Some(resp) = window {
+        let r = resp.response.rect;
+        state.playlists_window_pos = (r.min.x, r.min.y);
+        if resp.inner.is_some() {
+            state.playlists_window_size = (r.width(), r.height());
+        }
+    }
+}
+
+// Renders the playlist browser body, shared by the floating window and the dock tab
+pub fn show_playlists_contents(ui: &mut Ui) {
+    let mut state = APP_STATE.lock().unwrap();
+    let mut window_size = state.playlists_window_size; // Make mutable
+    let playlists = state.playlists.clone();
+    let user_id = state.user_id.clone().unwrap_or_default();
+    let mut view_mode = state.playlist_view_mode;
+    let scroll_offset = state.take_pending_scroll_offset("playlists", !playlists.is_empty());
+    drop(state);
+
+    // Add the new playlist button and view mode controls
+    ui.horizontal(|ui| {
+        if ui.button(format!("{} New playlist", egui_phosphor::bold::PLUS)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+            APP_STATE.lock().unwrap().playlist_draft = Some(PlaylistDraft::default());
+        }
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if ui.toggle_value(&mut (view_mode == ViewMode::List), &format!("{} List", egui_phosphor::bold::LIST)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+                view_mode = ViewMode::List;
+                window_size = (400.0, 600.0); // Allowed since mutable
+            }
             ui.add_space(8.0);
+            if ui.toggle_value(&mut (view_mode == ViewMode::Grid), &format!("{} Grid", egui_phosphor::bold::SQUARES_FOUR)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+                window_size = (800.0, 600.0); // Allowed since mutable
+                view_mode = ViewMode::Grid;
+            }
+            ui.label("View:");
+        });
+    });
+    ui.add_space(8.0);
 
-            let filtered: Vec<(String, String, String, String)> = playlists
-                .iter()
-                .map(|(name, owner, image_url, id, _total_tracks)|  // Prefix total_tracks
-                    (name.clone(), owner.clone(), image_url.clone(), id.clone())
-                )
-                .collect();
-            
+    // The grid view takes tracks, so each playlist poses as one with its owner as the artist
+    let filtered: Vec<TrackInfo> = playlists
+        .iter()
+        .map(|playlist| TrackInfo {
+            name: playlist.name.clone(),
+            artists: playlist.owner.clone(),
+            image_url: playlist.image_url.clone(),
+            uri: playlist.id.clone(),
+            ..Default::default()
+        })
+        .collect();
+    
 
-            match view_mode {
-                ViewMode::List => {
-                    egui::ScrollArea::vertical().show(ui, |ui| {
-                        for (name, owner, image_url, id, total_tracks) in playlists {
-                            let row_response = ui.horizontal(|ui| {
-                                render_square_with_image(ui, 40.0, &image_url);
-                                ui.vertical(|ui| {
-                                    ui.horizontal(|ui| {
-                                        ui.label(
-                                            egui::RichText::new(&name)
-                                                .size(16.0)
-                                                .strong()
-                                                .color(ui.visuals().strong_text_color())
-                                        );
-                                        ui.label(
-                                            egui::RichText::new(format!(" • {} tracks", total_tracks))
-                                                .size(14.0)
-                                                .color(ui.visuals().weak_text_color())
-                                        );
-                                    });
-                                    ui.label(
-                                        egui::RichText::new(&owner)
-                                            .size(14.0)
-                                            .color(ui.visuals().weak_text_color())
-                                    );
-                                });
-                            }).response;
+    let offset = match view_mode {
+        // The playlist browser has no table of its own
+        ViewMode::List | ViewMode::Table => {
+            let mut scroll_area = egui::ScrollArea::vertical();
+            if let Some(offset) = scroll_offset {
+                scroll_area = scroll_area.vertical_scroll_offset(offset);
+            }
+            scroll_area.show(ui, |ui| {
+                for (library_playlist, playlist) in playlists.iter().zip(&filtered) {
+                    let row_response = ui.horizontal(|ui| {
+                        render_square_with_image(ui, 40.0, &library_playlist.image_url);
+                        ui.vertical(|ui| {
+                            ui.horizontal(|ui| {
+                                ui.label(
+                                    egui::RichText::new(&library_playlist.name)
+                                        .size(16.0)
+                                        .strong()
+                                        .color(ui.visuals().strong_text_color())
+                                );
+                                ui.label(
+                                    egui::RichText::new(format!(" • {} tracks", library_playlist.total_tracks))
+                                        .size(14.0)
+                                        .color(ui.visuals().weak_text_color())
+                                );
+                            });
+                            ui.label(
+                                egui::RichText::new(playlist_subtitle(library_playlist))
+                                    .size(14.0)
+                                    .color(ui.visuals().weak_text_color())
+                            );
+                        });
+                    }).response;
 
-                            // Make the row clickable
-                            if row_response.interact(egui::Sense::click()).on_hover_cursor(CursorIcon::PointingHand).clicked() {
-                                let id = id.clone();
-                                let token = web_sys::window()
-                                    .and_then(|window| window.local_storage().ok().flatten())
-                                    .and_then(|storage| storage.get_item("spotify_token").ok().flatten())
-                                    .unwrap_or_default();
-                                
-                                wasm_bindgen_futures::spawn_local(async move {
-                                    crate::api_request::playlist_tracks::fetch_playlist_tracks(id, token).await;
-                                });
-                            }
-                            ui.add_space(4.0);
-                            ui.separator();
-                            ui.add_space(4.0);
-                        }
-                    });
-                },
-                ViewMode::Grid => {
-                    let enumerated: Vec<_> = filtered.iter().enumerate().collect();
-                    show_grid_view(
-                        ui,
-                        &enumerated,
-                        None,
-                        playlists.len(),
-                        playlists.len() as i32,
-                        ListViewMode::Playlists,
-                        None,
-                        &user_id
-                    );
+                    // Make the row clickable
+                    let row_response = row_response.interact(egui::Sense::click());
+                    show_context_menu(&row_response, &ListViewMode::Playlists, playlist, None);
+                    if row_response.on_hover_cursor(CursorIcon::PointingHand).clicked() {
+                        let id = library_playlist.id.clone();
+                        let token = web_sys::window()
+                            .and_then(|window| window.local_storage().ok().flatten())
+                            .and_then(|storage| storage.get_item("spotify_token").ok().flatten())
+                            .unwrap_or_default();
+                        
+                        wasm_bindgen_futures::spawn_local(async move {
+                            crate::api_request::playlist_tracks::fetch_playlist_tracks(id, token).await;
+                        });
+                    }
+                    ui.add_space(4.0);
+                    ui.separator();
+                    ui.add_space(4.0);
+                }
+            }).state.offset.y
+        },
+        ViewMode::Grid => {
+            let enumerated: Vec<_> = filtered.iter().enumerate().collect();
+            show_grid_view(
+                ui,
+                &enumerated,
+                None,
+                playlists.len(),
+                playlists.len() as i32,
+                &mut ListContext {
+                    mode: ListViewMode::Playlists,
+                    playlist_id: None,
+                    user_id: &user_id,
+                    reorderable: false,
+                    selection: &mut TrackSelection::default(), // Playlists aren't selectable
                 },
-            }
-        }
-    );
+                scroll_offset
+            )
+        },
+    };
 
     let mut state = APP_STATE.lock().unwrap();
-    state.playlists_window_open = playlists_window_open;
+    state.scroll_offsets.insert("playlists".to_string(), offset);
     state.playlists_window_size = window_size; // Update window_size in state
-    if let Some(resp) = window {
-        let r = resp.response.rect;
-        state.playlists_window_pos = (r.min.x, r.min.y);
+    state.playlist_view_mode = view_mode;
+}
+
+// Owner followed by the visibility, e.g. "Spotify • Public"
+fn playlist_subtitle(playlist: &PlaylistInfo) -> String {
+    let visibility = if playlist.collaborative {
+        "Collaborative"
+    } else if playlist.public {
+        "Public"
+    } else {
+        "Private"
+    };
+    format!("{} • {}", playlist.owner, visibility)
+}
+
+// Dialog for creating a playlist or editing one's details, shown while a draft exists
+pub fn show_playlist_dialog(ctx: &Context) {
+    let state = APP_STATE.lock().unwrap();
+    let mut draft = match state.playlist_draft.clone() {
+        Some(draft) => draft,
+        None => return,
+    };
+    drop(state);
+
+    let editing = draft.id.is_some();
+    let mut open = true;
+    let mut submitted = false;
+    let mut cancelled = false;
+    egui::Window::new(if editing { "Edit playlist" } else { "New playlist" })
+        .id(egui::Id::new("playlist_dialog"))
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            egui::Grid::new("playlist_dialog_fields").num_columns(2).spacing([8.0, 8.0]).show(ui, |ui| {
+                ui.label("Name");
+                ui.add(egui::TextEdit::singleline(&mut draft.name).hint_text("My playlist"));
+                ui.end_row();
+                ui.label("Description");
+                ui.add(egui::TextEdit::multiline(&mut draft.description).desired_rows(3));
+                ui.end_row();
+            });
+            ui.add_space(4.0);
+            // Spotify only allows collaboration on private playlists
+            if ui.checkbox(&mut draft.collaborative, "Collaborative")
+                .on_hover_text("Anyone you share it with can add, remove and reorder tracks")
+                .changed() && draft.collaborative {
+                draft.public = false;
+            }
+            ui.add_enabled(!draft.collaborative, egui::Checkbox::new(&mut draft.public, "Public"))
+                .on_disabled_hover_text("Collaborative playlists are always private");
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                let label = if editing { "Save" } else { "Create" };
+                if ui.add_enabled(!draft.name.trim().is_empty(), egui::Button::new(label)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+                    submitted = true;
+                }
+                if ui.button("Cancel").on_hover_cursor(CursorIcon::PointingHand).clicked() {
+                    cancelled = true;
+                }
+            });
+        });
+
+    let mut state = APP_STATE.lock().unwrap();
+    state.playlist_draft = (open && !submitted && !cancelled).then(|| draft.clone());
+    drop(state);
+
+    if submitted {
+        let token = web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .and_then(|storage| storage.get_item("spotify_token").ok().flatten())
+            .unwrap_or_default();
+        wasm_bindgen_futures::spawn_local(async move {
+            if editing {
+                crate::api_request::playlist_edit::update_playlist_details(token, draft).await;
+            } else {
+                crate::api_request::playlist_edit::create_playlist(token, draft).await;
+            }
+        });
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,160 @@
+use egui::{Context, CursorIcon, Ui};
+use crate::api_request::search::{search_catalog, SEARCH_PAGE_SIZE};
+use crate::api_request::token::get_token;
+use crate::ui::app_state::{window_content_size, SearchCategory, ViewMode, APP_STATE};
+use crate::ui::tracks_ui::{show_grid_view, show_list_view, ListContext, ListViewMode};
+use crate::ui::track_selection::show_selection_bar;
+
+pub fn show_search_window(ctx: &Context) {
+    let state = APP_STATE.lock().unwrap();
+    if !state.search_window_open {
+        return;
+    }
+
+    let mut search_window_open = state.search_window_open;
+    let window_pos = state.search_window_pos;
+    let window_size = state.search_window_size;
+    let constrain_rect = state.constrain_to_central_panel(ctx);
+    let layout_generation = state.layout_generation;
+    drop(state);
+
+    let window = egui::Window::new("Search Spotify")
+        .id(egui::Id::new(("search_window", layout_generation)))
+        .open(&mut search_window_open)
+        .current_pos([window_pos.0, window_pos.1])
+        .default_size(window_content_size(ctx, window_size))
+        .constrain_to(constrain_rect)
+        .show(ctx, |ui| {
+            show_search_contents(ui);
+        });
+
+    let mut state = APP_STATE.lock().unwrap();
+    state.search_window_open = search_window_open;
+    if let Some(resp) = window {
+        let r = resp.response.rect;
+        state.search_window_pos = (r.min.x, r.min.y);
+        if resp.inner.is_some() {
+            state.search_window_size = (r.width(), r.height());
+        }
+    }
+}
+
+fn start_search(query: String, category: Option<SearchCategory>, offset: u32) {
+    let token = match get_token() {
+        Some(token) => token,
+        None => return,
+    };
+    wasm_bindgen_futures::spawn_local(async move {
+        search_catalog(token, query, category, offset).await;
+    });
+}
+
+// Renders the catalog search body, shared by the floating window and the dock tab
+pub fn show_search_contents(ui: &mut Ui) {
+    let state = APP_STATE.lock().unwrap();
+    let mut query = state.catalog_query.clone();
+    let catalog = state.catalog.clone();
+    let mut category = state.catalog_category;
+    let mut view_mode = state.catalog_view_mode;
+    let user_id = state.user_id.clone().unwrap_or_default();
+    let mut selection = state.track_selections.get("search").cloned().unwrap_or_default();
+    drop(state);
+
+    ui.horizontal(|ui| {
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut query)
+                .hint_text("Songs, albums, artists or playlists")
+                .desired_width(250.0)
+        );
+        let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        let clicked = ui.button(format!("{} Search", egui_phosphor::bold::MAGNIFYING_GLASS))
+            .on_hover_cursor(CursorIcon::PointingHand)
+            .clicked();
+        if (submitted || clicked) && !query.trim().is_empty() {
+            let query = query.trim().to_string();
+            APP_STATE.lock().unwrap().catalog.query = query.clone();
+            start_search(query, None, 0);
+        }
+        if catalog.loading {
+            ui.spinner();
+        }
+    });
+    ui.add_space(4.0);
+
+    ui.horizontal(|ui| {
+        for option in SearchCategory::ALL {
+            let total = catalog.page(option).total;
+            let label = if catalog.query.is_empty() {
+                option.label().to_string()
+            } else {
+                format!("{} ({})", option.label(), total)
+            };
+            ui.selectable_value(&mut category, option, label);
+        }
+
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if ui.toggle_value(&mut (view_mode == ViewMode::List), format!("{} List", egui_phosphor::bold::LIST)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+                view_mode = ViewMode::List;
+            }
+            ui.add_space(8.0);
+            if ui.toggle_value(&mut (view_mode == ViewMode::Grid), format!("{} Grid", egui_phosphor::bold::SQUARES_FOUR)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+                view_mode = ViewMode::Grid;
+            }
+        });
+    });
+    ui.separator();
+
+    let page = catalog.page(category);
+    if catalog.query.is_empty() {
+        ui.label("Search the Spotify catalog to play, queue, like or add results to a playlist.");
+    } else if page.items.is_empty() && !catalog.loading {
+        ui.label(format!("No {} found for \"{}\".", category.label().to_lowercase(), catalog.query));
+    } else {
+        // Pagination controls above the results so they stay put while scrolling
+        ui.horizontal(|ui| {
+            let first = page.offset + 1;
+            let last = page.offset + page.items.len() as u32;
+            ui.label(format!("{}–{} of {}", first, last, page.total));
+            let has_previous = page.offset > 0;
+            let has_next = page.offset + SEARCH_PAGE_SIZE < page.total;
+            if ui.add_enabled(has_previous && !catalog.loading, egui::Button::new(egui_phosphor::bold::CARET_LEFT))
+                .on_hover_text("Previous page")
+                .clicked() {
+                start_search(catalog.query.clone(), Some(category), page.offset.saturating_sub(SEARCH_PAGE_SIZE));
+            }
+            if ui.add_enabled(has_next && !catalog.loading, egui::Button::new(egui_phosphor::bold::CARET_RIGHT))
+                .on_hover_text("Next page")
+                .clicked() {
+                start_search(catalog.query.clone(), Some(category), page.offset + SEARCH_PAGE_SIZE);
+            }
+        });
+        ui.add_space(4.0);
+
+        let mode = match category {
+            SearchCategory::Tracks => ListViewMode::Catalog,
+            SearchCategory::Albums | SearchCategory::Artists => ListViewMode::Contexts,
+            SearchCategory::Playlists => ListViewMode::Playlists,
+        };
+        // Only tracks can be selected
+        if category == SearchCategory::Tracks {
+            show_selection_bar(ui, &mut selection, &page.items, None, false);
+        }
+        let items: Vec<_> = page.items.iter().enumerate().collect();
+        let mut list = ListContext { mode, playlist_id: None, user_id: &user_id, reorderable: false, selection: &mut selection };
+        match view_mode {
+            ViewMode::Grid => {
+                show_grid_view(ui, &items, None, items.len(), items.len() as i32, &mut list, None);
+            }
+            // Results have no table of their own
+            ViewMode::List | ViewMode::Table => {
+                show_list_view(ui, &items, &mut list, None, None);
+            }
+        }
+    }
+
+    let mut state = APP_STATE.lock().unwrap();
+    state.catalog_query = query;
+    state.catalog_category = category;
+    state.catalog_view_mode = view_mode;
+    state.track_selections.insert("search".to_string(), selection);
+}
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use super::app_state::AppState;
+use super::dock::DockWorkspace;
+use super::layouts::LayoutPreset;
+use crate::api_request::token::get_token;
+use crate::storage::{load_session, save_session, save_tracks};
+use crate::utils::log_error;
+
+const SESSION_SAVE_INTERVAL_MS: f64 = 1000.0; // Window drags change the session every frame
+
+// The UI session that survives a reload: open windows and their placement, plus search and scroll state
+#[derive(Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    pub layout: LayoutPreset,
+    pub search_text: String,
+    pub scroll_offsets: HashMap<String, f32>,
+}
+
+impl SessionSnapshot {
+    fn capture(state: &AppState, dock: &DockWorkspace) -> Self {
+        SessionSnapshot {
+            layout: LayoutPreset::capture("Session".to_string(), state, dock),
+            search_text: state.search_text.clone(),
+            // Only keep offsets for views that are still open
+            scroll_offsets: state.scroll_offsets
+                .iter()
+                .filter(|(key, _)| match key.strip_prefix("playlist:") {
+                    Some(id) => state.playlist_windows.iter().any(|w| w.id == id && w.open),
+                    None => true,
+                })
+                .map(|(key, offset)| (key.clone(), *offset))
+                .collect(),
+        }
+    }
+}
+
+// Reopens the windows from the last session; their contents are refetched from cache or the API
+pub fn restore_session(state: &mut AppState, dock: &mut DockWorkspace) {
+    if get_token().is_none() {
+        return;
+    }
+    if let Some(session) = load_session() {
+        session.layout.apply(state, dock);
+        state.search_text = session.search_text;
+        state.pending_scroll_offsets = session.scroll_offsets;
+    }
+}
+
+#[derive(Default)]
+pub struct SessionSaver {
+    last_saved: String,
+    last_save_time: f64,
+    last_library_save_time: f64,
+}
+
+impl SessionSaver {
+    // Writes out the library cache that likes edited, at most once per interval since it can be large;
+    // returns true if edits are still waiting
+    pub fn save_library(&mut self, state: &mut AppState) -> bool {
+        if state.unsaved_library.is_none() {
+            return false;
+        }
+        let now = js_sys::Date::now();
+        if now - self.last_library_save_time < SESSION_SAVE_INTERVAL_MS {
+            return true;
+        }
+        if let Some(stored) = state.unsaved_library.take() {
+            if let Err(e) = save_tracks(&stored.tracks, stored.total) {
+                log_error(&format!("Failed to save tracks to storage: {}", e));
+            }
+        }
+        self.last_library_save_time = now;
+        false
+    }
+
+    // Saves the session when it changed, at most once per interval; returns true if a change is still waiting
+    pub fn save_if_changed(&mut self, state: &AppState, dock: &DockWorkspace) -> bool {
+        // Nothing worth restoring while logged out
+        if state.username.is_none() {
+            return false;
+        }
+
+        let session = match serde_json::to_string(&SessionSnapshot::capture(state, dock)) {
+            Ok(session) => session,
+            Err(_) => return false,
+        };
+        if session == self.last_saved {
+            return false;
+        }
+
+        let now = js_sys::Date::now();
+        if now - self.last_save_time < SESSION_SAVE_INTERVAL_MS {
+            return true;
+        }
+        if let Err(e) = save_session(&session) {
+            log_error(&format!("Failed to save session: {}", e));
+        }
+        self.last_saved = session;
+        self.last_save_time = now;
+        false
+    }
+}
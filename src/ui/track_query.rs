@@ -0,0 +1,422 @@
+use std::sync::Arc;
+use egui::{Id, Ui};
+use crate::api_request::models::TrackInfo;
+use crate::ui::TrackList;
+
+// Shown as hover text on the search boxes
+pub const QUERY_HELP: &str = "Words match title, artists or album, tolerating small typos and accents.\n\
+artist:radiohead  album:\"ok computer\"  title:creep\n\
+year:2010  year:>2010  year:2000..2009\n\
+duration:<3m  duration:>=4m30s  duration:2:30..3:30\n\
+explicit  -explicit  -artist:drake";
+
+#[derive(Clone, Copy, PartialEq)]
+enum TextField {
+    Any, // Title, artists or album
+    Title,
+    Artist,
+    Album,
+}
+
+enum Condition {
+    Text(TextField, Vec<String>), // Normalized words that must all match
+    Year(i64, i64),                // Inclusive range
+    Duration(i64, i64),            // Inclusive range in milliseconds
+    Explicit,
+}
+
+struct Term {
+    negated: bool,
+    condition: Condition,
+}
+
+// A parsed search box query; terms are ANDed together
+pub struct TrackQuery {
+    terms: Vec<Term>,
+}
+
+impl TrackQuery {
+    pub fn parse(query: &str) -> Self {
+        let terms = tokenize(query)
+            .into_iter()
+            .filter_map(|token| {
+                let (negated, token) = match token.strip_prefix('-') {
+                    Some(rest) if !rest.is_empty() => (true, rest.to_string()),
+                    _ => (false, token),
+                };
+                parse_condition(&token).map(|condition| Term { negated, condition })
+            })
+            .collect();
+        TrackQuery { terms }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    pub fn matches(&self, track: &TrackInfo) -> bool {
+        self.terms.iter().all(|term| term.condition.matches(track) != term.negated)
+    }
+}
+
+impl Condition {
+    fn matches(&self, track: &TrackInfo) -> bool {
+        match self {
+            Condition::Text(field, words) => {
+                let fields: Vec<&str> = match field {
+                    TextField::Any => vec![&track.name, &track.artists, &track.album],
+                    TextField::Title => vec![&track.name],
+                    TextField::Artist => vec![&track.artists],
+                    TextField::Album => vec![&track.album],
+                };
+                let haystack = normalize(&fields.join(" "));
+                words.iter().all(|word| fuzzy_contains(&haystack, word))
+            }
+            Condition::Year(from, to) => track.release_date
+                .get(..4)
+                .and_then(|year| year.parse::<i64>().ok())
+                .is_some_and(|year| (*from..=*to).contains(&year)),
+            Condition::Duration(from, to) => (*from..=*to).contains(&(track.duration_ms as i64)),
+            Condition::Explicit => track.explicit,
+        }
+    }
+}
+
+// Splits on whitespace, keeping double-quoted phrases (including field:"...") together
+fn tokenize(query: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in query.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+// Unknown fields and malformed values fall back to plain text so nothing typed is silently dropped
+fn parse_condition(token: &str) -> Option<Condition> {
+    if token.eq_ignore_ascii_case("explicit") || token.eq_ignore_ascii_case("is:explicit") {
+        return Some(Condition::Explicit);
+    }
+
+    if let Some((field, value)) = token.split_once(':') {
+        let condition = match field.to_lowercase().as_str() {
+            "artist" | "artists" => text_condition(TextField::Artist, value),
+            "album" => text_condition(TextField::Album, value),
+            "title" | "track" | "name" => text_condition(TextField::Title, value),
+            "year" => parse_range(value, |v| v.parse().ok())
+                .map(|(from, to)| Condition::Year(from, to)),
+            "duration" | "length" => parse_range(value, parse_duration)
+                .map(|(from, to)| Condition::Duration(from, to)),
+            _ => None,
+        };
+        if condition.is_some() {
+            return condition;
+        }
+    }
+
+    text_condition(TextField::Any, token)
+}
+
+fn text_condition(field: TextField, value: &str) -> Option<Condition> {
+    let words = words(&normalize(value));
+    if words.is_empty() {
+        None
+    } else {
+        Some(Condition::Text(field, words))
+    }
+}
+
+// Accepts "v", ">v", ">=v", "<v", "<=v" and "a..b"
+fn parse_range(value: &str, parse: impl Fn(&str) -> Option<i64>) -> Option<(i64, i64)> {
+    if let Some((from, to)) = value.split_once("..") {
+        return Some((parse(from)?, parse(to)?));
+    }
+    if let Some(v) = value.strip_prefix(">=") {
+        return Some((parse(v)?, i64::MAX));
+    }
+    if let Some(v) = value.strip_prefix("<=") {
+        return Some((i64::MIN, parse(v)?));
+    }
+    if let Some(v) = value.strip_prefix('>') {
+        return Some((parse(v)?.saturating_add(1), i64::MAX));
+    }
+    if let Some(v) = value.strip_prefix('<') {
+        return Some((i64::MIN, parse(v)?.saturating_sub(1)));
+    }
+    let v = parse(value)?;
+    Some((v, v))
+}
+
+// "3m", "3m30s", "210s", "3:30" or a bare number of seconds, in milliseconds
+fn parse_duration(value: &str) -> Option<i64> {
+    let value = value.trim().to_lowercase();
+    if let Some((minutes, seconds)) = value.split_once(':') {
+        return Some((minutes.parse::<i64>().ok()? * 60 + seconds.parse::<i64>().ok()?) * 1000);
+    }
+    if let Ok(seconds) = value.parse::<i64>() {
+        return Some(seconds * 1000);
+    }
+
+    let mut total = 0;
+    let mut number = String::new();
+    for c in value.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'm' | 's' => {
+                let amount = number.parse::<i64>().ok()?;
+                total += if c == 'm' { amount * 60 } else { amount };
+                number.clear();
+            }
+            _ => return None,
+        }
+    }
+    if !number.is_empty() {
+        return None;
+    }
+    Some(total * 1000)
+}
+
+// Lowercases and strips accents, so "beyonce" finds "Beyoncé"
+fn normalize(text: &str) -> String {
+    text.chars().flat_map(char::to_lowercase).map(fold_diacritic).collect()
+}
+
+pub fn fold_diacritic(c: char) -> char {
+    match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => 'a',
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => 'c',
+        'ď' | 'đ' => 'd',
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => 'e',
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => 'g',
+        'ĥ' | 'ħ' => 'h',
+        'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => 'i',
+        'ĵ' => 'j',
+        'ķ' => 'k',
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => 'l',
+        'ñ' | 'ń' | 'ņ' | 'ň' => 'n',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => 'o',
+        'ŕ' | 'ŗ' | 'ř' => 'r',
+        'ś' | 'ŝ' | 'ş' | 'š' => 's',
+        'ţ' | 'ť' | 'ŧ' => 't',
+        'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => 'u',
+        'ŵ' => 'w',
+        'ý' | 'ÿ' | 'ŷ' => 'y',
+        'ź' | 'ż' | 'ž' => 'z',
+        c => c,
+    }
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// Substring match, or a word in the haystack whose start is within a typo or two of the needle
+fn fuzzy_contains(haystack: &str, needle: &str) -> bool {
+    if haystack.contains(needle) {
+        return true;
+    }
+    let needle: Vec<char> = needle.chars().collect();
+    let max_typos = match needle.len() {
+        0..=3 => return false,
+        4..=7 => 1,
+        _ => 2,
+    };
+    haystack
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|word| {
+            let word: Vec<char> = word.chars().take(needle.len() + 1).collect();
+            // Compare against the word's start so partially typed words still match
+            (needle.len() - 1..=needle.len() + 1)
+                .filter(|&len| len <= word.len())
+                .any(|len| edit_distance(&needle, &word[..len]) <= max_typos)
+        })
+}
+
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// Search box shared by the track views, with the query syntax on hover
+pub fn show_search_box(ui: &mut Ui, search_text: &mut String) {
+    ui.label(format!("{} Search:", egui_phosphor::bold::MAGNIFYING_GLASS));
+    // Calculate desired width based on text content, with minimum width
+    let desired_width = (search_text.len() as f32 * 8.0).max(100.0);
+    ui.add(
+        egui::TextEdit::singleline(search_text)
+            .hint_text("artist:, year:>2010, -explicit")
+            .desired_width(desired_width)
+    ).on_hover_text(QUERY_HELP);
+}
+
+// Filters tracks by a search box query, pairing each with its position in the playlist or library.
+// Matching normalizes every track, so the matching indices are cached per view until the query
+// changes or the track list is edited
+pub fn filter_tracks<'a>(ui: &Ui, view_key: &str, query: &str, tracks: &'a TrackList) -> Vec<(usize, &'a TrackInfo)> {
+    let parsed = TrackQuery::parse(query);
+    if parsed.is_empty() {
+        return tracks.iter().enumerate().map(|(index, track)| (tracks.position(index), track)).collect();
+    }
+
+    let cache_id = Id::new(("track_query", view_key));
+    let cache_key = (query.to_string(), tracks.generation());
+    let cached = ui.ctx().data(|data| data.get_temp::<((String, u64), Arc<Vec<usize>>)>(cache_id));
+    let indices = match cached {
+        Some((key, indices)) if key == cache_key => indices,
+        _ => {
+            let indices: Arc<Vec<usize>> = Arc::new(
+                tracks.iter()
+                    .enumerate()
+                    .filter(|(_, track)| parsed.matches(track))
+                    .map(|(index, _)| index)
+                    .collect()
+            );
+            ui.ctx().data_mut(|data| data.insert_temp(cache_id, (cache_key, indices.clone())));
+            indices
+        }
+    };
+    indices.iter().map(|&index| (tracks.position(index), &tracks[index])).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(name: &str, artists: &str, album: &str) -> TrackInfo {
+        TrackInfo {
+            name: name.to_string(),
+            artists: artists.to_string(),
+            album: album.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn matches(query: &str, track: &TrackInfo) -> bool {
+        TrackQuery::parse(query).matches(track)
+    }
+
+    #[test]
+    fn parse_range_forms() {
+        let number = |v: &str| v.parse().ok();
+        assert_eq!(parse_range("2010", number), Some((2010, 2010)));
+        assert_eq!(parse_range(">2010", number), Some((2011, i64::MAX)));
+        assert_eq!(parse_range(">=2010", number), Some((2010, i64::MAX)));
+        assert_eq!(parse_range("<2010", number), Some((i64::MIN, 2009)));
+        assert_eq!(parse_range("<=2010", number), Some((i64::MIN, 2010)));
+        assert_eq!(parse_range("2000..2009", number), Some((2000, 2009)));
+        assert_eq!(parse_range("2000..", number), None);
+        assert_eq!(parse_range("soon", number), None);
+    }
+
+    #[test]
+    fn parse_duration_forms() {
+        assert_eq!(parse_duration("3m"), Some(180_000));
+        assert_eq!(parse_duration("3m30s"), Some(210_000));
+        assert_eq!(parse_duration("210s"), Some(210_000));
+        assert_eq!(parse_duration("3:30"), Some(210_000));
+        assert_eq!(parse_duration("90"), Some(90_000));
+        assert_eq!(parse_duration("3M"), Some(180_000));
+        assert_eq!(parse_duration("3m30"), None);
+        assert_eq!(parse_duration("3h"), None);
+    }
+
+    #[test]
+    fn year_and_duration_filters() {
+        let mut song = track("Song", "Band", "Record");
+        song.release_date = "2012-05-01".to_string();
+        song.duration_ms = 200_000;
+        assert!(matches("year:>2010", &song));
+        assert!(matches("year:2010..2012", &song));
+        assert!(!matches("year:<2012", &song));
+        assert!(matches("duration:3m..3:30", &song));
+        assert!(!matches("duration:>=4m", &song));
+
+        // Tracks without a release date never match a year
+        song.release_date.clear();
+        assert!(!matches("year:<3000", &song));
+    }
+
+    #[test]
+    fn quoted_phrases_stay_together() {
+        assert_eq!(tokenize("album:\"ok computer\" creep"), vec!["album:ok computer", "creep"]);
+        let creep = track("Creep", "Radiohead", "Pablo Honey");
+        let paranoid = track("Paranoid Android", "Radiohead", "OK Computer");
+        assert!(matches("album:\"ok computer\"", &paranoid));
+        assert!(!matches("album:\"ok computer\"", &creep));
+        assert!(!matches("title:\"ok computer\"", &paranoid));
+    }
+
+    #[test]
+    fn negation() {
+        let mut song = track("Song", "Drake", "Views");
+        assert!(!matches("-artist:drake", &song));
+        assert!(matches("-explicit", &song));
+        song.explicit = true;
+        assert!(matches("explicit", &song));
+        assert!(!matches("-explicit", &song));
+        // A lone dash has no words to search for
+        assert!(TrackQuery::parse("-").is_empty());
+    }
+
+    #[test]
+    fn unknown_fields_are_text() {
+        let song = track("mood:happy", "Band", "Record");
+        assert!(matches("mood:happy", &song));
+        assert!(matches("year:soon", &track("Year Soon", "", "")));
+    }
+
+    #[test]
+    fn diacritics_fold_both_ways() {
+        assert_eq!(fold_diacritic('é'), 'e');
+        assert_eq!(fold_diacritic('ø'), 'o');
+        assert_eq!(fold_diacritic('ß'), 'ß');
+        assert_eq!(normalize("Beyoncé Ñandú"), "beyonce nandu");
+        let song = track("Halo", "Beyoncé", "I Am... Sasha Fierce");
+        assert!(matches("beyonce", &song));
+        assert!(matches("BEYONCÉ", &song));
+    }
+
+    #[test]
+    fn edit_distance_counts_edits() {
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+        assert_eq!(edit_distance(&chars("radiohead"), &chars("radiohead")), 0);
+        assert_eq!(edit_distance(&chars("radiohed"), &chars("radiohead")), 1);
+        assert_eq!(edit_distance(&chars("kitten"), &chars("sitting")), 3);
+        assert_eq!(edit_distance(&chars(""), &chars("abc")), 3);
+    }
+
+    #[test]
+    fn typo_tolerance_scales_with_length() {
+        let song = track("Paranoid Android", "Radiohead", "OK Computer");
+        assert!(matches("radiohed", &song)); // One typo
+        assert!(matches("radiohaed", &song)); // Two typos in a long word
+        assert!(!matches("raidoheda", &song));
+        assert!(matches("andro", &song)); // Partially typed
+        assert!(!matches("ok", &track("Song", "Band", "Record")));
+        // Short words must match exactly
+        assert!(!matches("cat", &track("Cut", "", "")));
+    }
+}
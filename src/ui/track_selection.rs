@@ -0,0 +1,233 @@
+use std::collections::HashSet;
+use egui::{CursorIcon, Modifiers, Ui};
+use crate::api_request::models::TrackInfo;
+use crate::api_request::token::get_token;
+use crate::ui::tracks_ui::show_playlist_picker;
+
+// Selected tracks of one view, keyed by URI so the selection survives tracks being added,
+// removed or reordered underneath it
+#[derive(Clone, Default)]
+pub struct TrackSelection {
+    uris: HashSet<String>,
+    anchor: Option<String>, // Last plain or ctrl click, where shift ranges start
+}
+
+impl TrackSelection {
+    pub fn is_empty(&self) -> bool {
+        self.uris.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.uris.len()
+    }
+
+    pub fn contains(&self, uri: &str) -> bool {
+        self.uris.contains(uri)
+    }
+
+    pub fn clear(&mut self) {
+        self.uris.clear();
+        self.anchor = None;
+    }
+
+    // Applies a click on a row of the displayed tracks: ctrl/cmd toggles the row and shift selects
+    // from the anchor through it. Returns false for a plain click, which the row handles itself
+    pub fn handle_click(&mut self, modifiers: Modifiers, tracks: &[(usize, &TrackInfo)], uri: &str) -> bool {
+        if modifiers.shift {
+            let position = |uri: &str| tracks.iter().position(|(_, track)| track.uri == uri);
+            let end = position(uri);
+            let start = self.anchor.as_deref().and_then(position).or(end);
+            if let (Some(start), Some(end)) = (start, end) {
+                if !modifiers.command {
+                    self.uris.clear();
+                }
+                for (_, track) in &tracks[start.min(end)..=start.max(end)] {
+                    self.uris.insert(track.uri.clone());
+                }
+            }
+            return true;
+        }
+
+        if modifiers.command {
+            if !self.uris.remove(uri) {
+                self.uris.insert(uri.to_string());
+            }
+            self.anchor = Some(uri.to_string());
+            return true;
+        }
+
+        self.uris.clear();
+        self.anchor = Some(uri.to_string());
+        false
+    }
+
+    // Selected tracks in list order
+    pub fn selected<'a>(&self, tracks: impl IntoIterator<Item = &'a TrackInfo>) -> Vec<&'a TrackInfo> {
+        tracks.into_iter().filter(|track| self.uris.contains(&track.uri)).collect()
+    }
+}
+
+// Count of selected tracks with the actions that apply to all of them. `tracks` is the view's
+// whole list, so tracks hidden by the search box stay part of the selection
+pub fn show_selection_bar(ui: &mut Ui, selection: &mut TrackSelection, tracks: &[TrackInfo], playlist_id: Option<&str>, in_liked_songs: bool) {
+    if selection.is_empty() {
+        return;
+    }
+    // Selected tracks that have since left the list (e.g. another search page) don't count
+    let selected: Vec<TrackInfo> = selection.selected(tracks).into_iter().cloned().collect();
+    if selected.is_empty() {
+        return;
+    }
+    let token = get_token().unwrap_or_default();
+    let uris: Vec<String> = selected.iter().map(|track| track.uri.clone()).collect();
+
+    ui.horizontal_wrapped(|ui| {
+        ui.label(egui::RichText::new(format!("{} selected", selected.len())).strong());
+        ui.separator();
+
+        ui.menu_button(format!("{} Add to playlist", egui_phosphor::bold::LIST_PLUS), |ui| {
+            show_playlist_picker(ui, &token, &uris);
+        });
+        if ui.button(format!("{} Queue all", egui_phosphor::bold::QUEUE)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+            let uris = uris.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                for uri in uris {
+                    crate::api_request::track_status::add_to_queue(uri).await;
+                }
+            });
+        }
+        if !in_liked_songs && ui.button(format!("{} Like", egui_phosphor::bold::HEART)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+            let token = token.clone();
+            let tracks = selected.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                crate::api_request::library::set_tracks_liked(token, tracks, true).await;
+            });
+        }
+        if ui.button(format!("{} Unlike", egui_phosphor::bold::HEART_BREAK)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+            let token = token.clone();
+            let tracks = selected.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                crate::api_request::library::set_tracks_liked(token, tracks, false).await;
+            });
+            if in_liked_songs {
+                selection.clear();
+            }
+        }
+        if let Some(playlist_id) = playlist_id {
+            if ui.button(format!("{} Remove from playlist", egui_phosphor::bold::TRASH)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+                let token = token.clone();
+                let playlist_id = playlist_id.to_string();
+                let uris = uris.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    crate::api_request::playlist_edit::remove_tracks_from_playlist(token, playlist_id, uris).await;
+                });
+                selection.clear();
+            }
+        }
+        if ui.button(format!("{} Copy URIs", egui_phosphor::bold::COPY)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+            ui.ctx().copy_text(uris.join("\n"));
+        }
+        if ui.button(format!("{} Copy links", egui_phosphor::bold::LINK)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+            let links: Vec<String> = selected.iter().map(|track| spotify_link(&track.uri)).collect();
+            ui.ctx().copy_text(links.join("\n"));
+        }
+        if ui.button(format!("{} Clear", egui_phosphor::bold::X)).on_hover_text("Clear selection").on_hover_cursor(CursorIcon::PointingHand).clicked() {
+            selection.clear();
+        }
+    });
+    ui.add_space(4.0);
+}
+
+// open.spotify.com link for a track, episode, album, artist or playlist URI
+pub fn spotify_link(uri: &str) -> String {
+    let mut parts = uri.split(':').skip(1);
+    match (parts.next(), parts.next()) {
+        (Some(kind), Some(id)) => format!("https://open.spotify.com/{}/{}", kind, id),
+        _ => uri.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracks(count: usize) -> Vec<TrackInfo> {
+        (0..count)
+            .map(|i| TrackInfo { uri: format!("spotify:track:{}", i), ..Default::default() })
+            .collect()
+    }
+
+    fn click(selection: &mut TrackSelection, modifiers: Modifiers, tracks: &[TrackInfo], index: usize) -> bool {
+        let shown: Vec<_> = tracks.iter().enumerate().collect();
+        selection.handle_click(modifiers, &shown, &tracks[index].uri)
+    }
+
+    fn selected_indices(selection: &TrackSelection, tracks: &[TrackInfo]) -> Vec<usize> {
+        (0..tracks.len()).filter(|&i| selection.contains(&tracks[i].uri)).collect()
+    }
+
+    #[test]
+    fn plain_click_sets_anchor_without_selecting() {
+        let tracks = tracks(5);
+        let mut selection = TrackSelection::default();
+        assert!(click(&mut selection, Modifiers::COMMAND, &tracks, 0));
+        assert!(!click(&mut selection, Modifiers::NONE, &tracks, 2));
+        assert!(selection.is_empty());
+
+        // The plain click moved the anchor, so a shift click ranges from it
+        assert!(click(&mut selection, Modifiers::SHIFT, &tracks, 4));
+        assert_eq!(selected_indices(&selection, &tracks), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn ctrl_click_toggles() {
+        let tracks = tracks(5);
+        let mut selection = TrackSelection::default();
+        click(&mut selection, Modifiers::COMMAND, &tracks, 1);
+        click(&mut selection, Modifiers::COMMAND, &tracks, 3);
+        assert_eq!(selected_indices(&selection, &tracks), vec![1, 3]);
+        click(&mut selection, Modifiers::COMMAND, &tracks, 1);
+        assert_eq!(selected_indices(&selection, &tracks), vec![3]);
+    }
+
+    #[test]
+    fn shift_click_selects_range_in_either_direction() {
+        let tracks = tracks(6);
+        let mut selection = TrackSelection::default();
+        click(&mut selection, Modifiers::COMMAND, &tracks, 4);
+        click(&mut selection, Modifiers::SHIFT, &tracks, 1);
+        assert_eq!(selected_indices(&selection, &tracks), vec![1, 2, 3, 4]);
+
+        // A later shift click replaces the range, keeping the same anchor
+        click(&mut selection, Modifiers::SHIFT, &tracks, 5);
+        assert_eq!(selected_indices(&selection, &tracks), vec![4, 5]);
+    }
+
+    #[test]
+    fn ctrl_shift_click_adds_range() {
+        let tracks = tracks(6);
+        let mut selection = TrackSelection::default();
+        click(&mut selection, Modifiers::COMMAND, &tracks, 0);
+        click(&mut selection, Modifiers::COMMAND, &tracks, 3);
+        click(&mut selection, Modifiers::SHIFT | Modifiers::COMMAND, &tracks, 5);
+        assert_eq!(selected_indices(&selection, &tracks), vec![0, 3, 4, 5]);
+    }
+
+    #[test]
+    fn shift_click_without_anchor_selects_one() {
+        let tracks = tracks(3);
+        let mut selection = TrackSelection::default();
+        assert!(click(&mut selection, Modifiers::SHIFT, &tracks, 2));
+        assert_eq!(selected_indices(&selection, &tracks), vec![2]);
+    }
+
+    #[test]
+    fn selected_keeps_list_order() {
+        let tracks = tracks(4);
+        let mut selection = TrackSelection::default();
+        click(&mut selection, Modifiers::COMMAND, &tracks, 3);
+        click(&mut selection, Modifiers::COMMAND, &tracks, 0);
+        let uris: Vec<_> = selection.selected(&tracks).into_iter().map(|t| t.uri.as_str()).collect();
+        assert_eq!(uris, vec!["spotify:track:0", "spotify:track:3"]);
+    }
+}
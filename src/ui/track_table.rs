@@ -0,0 +1,306 @@
+use std::cmp::Ordering;
+use std::sync::Arc;
+use egui::{CursorIcon, Id, Ui};
+use egui_extras::{Column, TableBuilder};
+use serde::{Deserialize, Serialize};
+use crate::api_request::models::TrackInfo;
+use crate::mediaplayer::scrubber::time_stamp_to_string;
+use crate::storage::save_table_configs;
+use crate::ui::APP_STATE;
+use crate::ui::tracks_ui::{show_context_menu, show_like_button, show_track_actions, start_track_drag, ListViewMode};
+use crate::ui::track_selection::TrackSelection;
+use crate::utils::log_error;
+
+const TABLE_ROW_HEIGHT: f32 = 24.0;
+const MAX_SORT_KEYS: usize = 3; // Older tie-breakers past this rarely change the order
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TrackColumn {
+    Title,
+    Artists,
+    Album,
+    Duration,
+    DateAdded,
+    Popularity,
+}
+
+impl TrackColumn {
+    const ALL: [TrackColumn; 6] = [
+        TrackColumn::Title,
+        TrackColumn::Artists,
+        TrackColumn::Album,
+        TrackColumn::Duration,
+        TrackColumn::DateAdded,
+        TrackColumn::Popularity,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            TrackColumn::Title => "Title",
+            TrackColumn::Artists => "Artists",
+            TrackColumn::Album => "Album",
+            TrackColumn::Duration => "Duration",
+            TrackColumn::DateAdded => "Date Added",
+            TrackColumn::Popularity => "Popularity",
+        }
+    }
+
+    fn default_width(self) -> f32 {
+        match self {
+            TrackColumn::Title | TrackColumn::Artists | TrackColumn::Album => 200.0,
+            TrackColumn::Duration | TrackColumn::Popularity => 80.0,
+            TrackColumn::DateAdded => 100.0,
+        }
+    }
+
+    // Text columns compare case-insensitively; added_at is ISO 8601 so it sorts as a string
+    fn compare(self, a: &TrackInfo, b: &TrackInfo) -> Ordering {
+        match self {
+            TrackColumn::Title => compare_ignore_case(&a.name, &b.name),
+            TrackColumn::Artists => compare_ignore_case(&a.artists, &b.artists),
+            TrackColumn::Album => compare_ignore_case(&a.album, &b.album),
+            TrackColumn::Duration => a.duration_ms.cmp(&b.duration_ms),
+            TrackColumn::DateAdded => a.added_at.cmp(&b.added_at),
+            TrackColumn::Popularity => a.popularity.cmp(&b.popularity),
+        }
+    }
+
+    fn cell_text(self, track: &TrackInfo) -> String {
+        match self {
+            TrackColumn::Title => track.name.clone(),
+            TrackColumn::Artists => track.artists.clone(),
+            TrackColumn::Album => track.album.clone(),
+            TrackColumn::Duration => time_stamp_to_string(track.duration_ms as f64),
+            TrackColumn::DateAdded => track.added_at.chars().take(10).collect(), // YYYY-MM-DD
+            TrackColumn::Popularity => track.popularity.to_string(),
+        }
+    }
+}
+
+// Compares without allocating lowercase copies, since a sort makes n log n comparisons
+fn compare_ignore_case(a: &str, b: &str) -> Ordering {
+    a.chars().flat_map(char::to_lowercase).cmp(b.chars().flat_map(char::to_lowercase))
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableColumn {
+    pub column: TrackColumn,
+    pub visible: bool,
+    pub width: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SortKey {
+    pub column: TrackColumn,
+    pub ascending: bool,
+}
+
+// Column layout and sort order of one window's table, persisted per window
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableConfig {
+    pub columns: Vec<TableColumn>,
+    pub sort: Vec<SortKey>, // Primary key first
+}
+
+impl Default for TableConfig {
+    fn default() -> Self {
+        TableConfig {
+            columns: TrackColumn::ALL
+                .iter()
+                .map(|&column| TableColumn {
+                    column,
+                    visible: true,
+                    width: column.default_width(),
+                })
+                .collect(),
+            sort: Vec::new(),
+        }
+    }
+}
+
+impl TableConfig {
+    // Clicking the primary column flips its direction; any other column becomes primary
+    // and the previous keys break ties
+    fn sort_by_column(&mut self, column: TrackColumn) {
+        match self.sort.first_mut() {
+            Some(primary) if primary.column == column => primary.ascending = !primary.ascending,
+            _ => {
+                self.sort.retain(|key| key.column != column);
+                self.sort.insert(0, SortKey { column, ascending: true });
+                self.sort.truncate(MAX_SORT_KEYS);
+            }
+        }
+    }
+
+    // Indices into `tracks` in sorted order. Stable, so tracks that tie on every key keep their library order
+    fn sort_order(&self, tracks: &[(usize, &TrackInfo)]) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..tracks.len()).collect();
+        if !self.sort.is_empty() {
+            order.sort_by(|&a, &b| {
+                let (a, b) = (tracks[a].1, tracks[b].1);
+                self.sort.iter().fold(Ordering::Equal, |ordering, key| {
+                    ordering.then_with(|| {
+                        let ordering = key.column.compare(a, b);
+                        if key.ascending { ordering } else { ordering.reverse() }
+                    })
+                })
+            });
+        }
+        order
+    }
+}
+
+// The sort order of a table, cached until the sort keys, the track list or the search change.
+// `source` is the track list's generation and the search the rows were filtered by
+fn cached_sort_order(ui: &Ui, table_key: &str, source: (u64, &str), config: &TableConfig, tracks: &[(usize, &TrackInfo)]) -> Arc<Vec<usize>> {
+    let cache_id = Id::new(("track_sort", table_key));
+    let cache_key = (source.0, source.1.to_string(), config.sort.clone());
+    let cached = ui.ctx().data(|data| data.get_temp::<((u64, String, Vec<SortKey>), Arc<Vec<usize>>)>(cache_id));
+    match cached {
+        Some((key, order)) if key == cache_key => order,
+        _ => {
+            let order = Arc::new(config.sort_order(tracks));
+            ui.ctx().data_mut(|data| data.insert_temp(cache_id, (cache_key, order.clone())));
+            order
+        }
+    }
+}
+
+// Sortable table of tracks using the column config stored under table_key. `source` is the generation
+// of the track list and the search the tracks were filtered by, keying the cached sort order.
+// Returns the vertical scroll offset so callers can remember it
+pub fn show_table_view(ui: &mut Ui, tracks: &[(usize, &TrackInfo)], table_key: &str, source: (u64, &str), playlist_id: Option<&str>, user_id: &str, scroll_offset: Option<f32>) -> f32 {
+    let saved_config = APP_STATE.lock().unwrap().table_configs.get(table_key).cloned().unwrap_or_default();
+    let mut config = saved_config.clone();
+    let order = cached_sort_order(ui, table_key, source, &saved_config, tracks);
+    let sorted: Vec<(usize, &TrackInfo)> = order.iter().map(|&index| tracks[index]).collect();
+    let offset = show_table(ui, &sorted, &mut config, table_key, playlist_id, user_id, scroll_offset);
+
+    if config != saved_config {
+        // Rows were laid out in the old order, so show the new one right away
+        ui.ctx().request_repaint();
+        let mut state = APP_STATE.lock().unwrap();
+        state.table_configs.insert(table_key.to_string(), config);
+        if let Err(e) = save_table_configs(&state.table_configs) {
+            log_error(&format!("Failed to save table columns: {}", e));
+        }
+    }
+    offset
+}
+
+// `sorted` is already in the config's sort order
+fn show_table(ui: &mut Ui, sorted: &[(usize, &TrackInfo)], config: &mut TableConfig, table_key: &str, playlist_id: Option<&str>, user_id: &str, scroll_offset: Option<f32>) -> f32 {
+    ui.horizontal(|ui| {
+        ui.menu_button(format!("{} Columns", egui_phosphor::bold::COLUMNS), |ui| {
+            for table_column in &mut config.columns {
+                ui.checkbox(&mut table_column.visible, table_column.column.label());
+            }
+            ui.separator();
+            if ui.button("Reset columns").on_hover_cursor(CursorIcon::PointingHand).clicked() {
+                config.columns = TableConfig::default().columns;
+                ui.close_menu();
+            }
+        });
+        if !config.sort.is_empty() && ui.button("Clear sort").on_hover_cursor(CursorIcon::PointingHand).clicked() {
+            config.sort.clear();
+        }
+    });
+
+    let visible: Vec<TrackColumn> = config.columns
+        .iter()
+        .filter(|c| c.visible)
+        .map(|c| c.column)
+        .collect();
+    if visible.is_empty() {
+        ui.label("All columns are hidden. Pick some from the Columns menu.");
+        return 0.0;
+    }
+
+    let mut clicked_column = None;
+    let mut widths = Vec::new();
+
+    let mut table = TableBuilder::new(ui)
+        .id_salt(("track_table", table_key, &visible))
+        .striped(true)
+        .resizable(true)
+        .sense(egui::Sense::click_and_drag())
+        .auto_shrink([false, false])
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center));
+    if let Some(offset) = scroll_offset {
+        table = table.vertical_scroll_offset(offset);
+    }
+    // Fixed heart and actions column ahead of the configurable ones
+    table = table.column(Column::exact(48.0));
+    for table_column in config.columns.iter().filter(|c| c.visible) {
+        table = table.column(Column::initial(table_column.width).at_least(40.0).clip(true));
+    }
+
+    let offset = table
+        .header(TABLE_ROW_HEIGHT, |mut header| {
+            header.col(|_| {});
+            for &column in &visible {
+                header.col(|ui| {
+                    let arrow = config.sort
+                        .iter()
+                        .position(|key| key.column == column)
+                        .map(|position| {
+                            let key = config.sort[position];
+                            let arrow = if key.ascending { egui_phosphor::bold::CARET_UP } else { egui_phosphor::bold::CARET_DOWN };
+                            // Secondary keys show their rank so the tie-break order is visible
+                            if position == 0 { format!(" {}", arrow) } else { format!(" {}{}", arrow, position + 1) }
+                        })
+                        .unwrap_or_default();
+                    if ui.add(egui::Button::new(egui::RichText::new(format!("{}{}", column.label(), arrow)).strong()).frame(false))
+                        .on_hover_text("Sort by this column")
+                        .on_hover_cursor(CursorIcon::PointingHand)
+                        .clicked() {
+                        clicked_column = Some(column);
+                    }
+                });
+            }
+        })
+        .body(|body| {
+            widths = body.widths().to_vec();
+            body.rows(TABLE_ROW_HEIGHT, sorted.len(), |mut row| {
+                let (original_index, track) = sorted[row.index()];
+                row.col(|ui| {
+                    show_like_button(ui, track, playlist_id.is_none());
+                    ui.menu_button(egui_phosphor::bold::DOTS_THREE, |ui| {
+                        show_track_actions(ui, track, playlist_id);
+                    });
+                });
+                for &column in &visible {
+                    row.col(|ui| {
+                        ui.add(egui::Label::new(column.cell_text(track)).truncate().selectable(false));
+                    });
+                }
+
+                // Sorted rows aren't playlist positions, so drops onto the table append instead
+                start_track_drag(&row.response(), track, playlist_id, original_index, &TrackSelection::default(), &[]);
+                show_context_menu(&row.response(), &ListViewMode::Tracks, track, playlist_id);
+                if row.response().on_hover_cursor(CursorIcon::PointingHand).clicked() {
+                    let uri = track.uri.clone();
+                    let context_uri = if let Some(id) = playlist_id {
+                        format!("spotify:playlist:{}", id)
+                    } else {
+                        format!("spotify:user:{}:collection", user_id)
+                    };
+                    wasm_bindgen_futures::spawn_local(async move {
+                        crate::api_request::track_status::play_track_with_context(uri, context_uri, original_index).await;
+                    });
+                }
+            });
+        })
+        .state
+        .offset
+        .y;
+
+    // Keep resized widths, rounded so sub-pixel jitter doesn't count as a change
+    for (table_column, width) in config.columns.iter_mut().filter(|c| c.visible).zip(widths.into_iter().skip(1)) {
+        table_column.width = width.round();
+    }
+    if let Some(column) = clicked_column {
+        config.sort_by_column(column);
+    }
+    offset
+}
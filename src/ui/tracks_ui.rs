@@ -1,12 +1,18 @@
 use egui::Ui;
 use egui_extras::{TableBuilder, Column};
 use crate::api_request::imagerender::get_or_load_image;
+use crate::api_request::models::TrackInfo;
+use crate::api_request::token::get_token;
+use crate::ui::{PlaylistDraft, APP_STATE};
+use crate::ui::track_selection::{spotify_link, TrackSelection};
 use egui::CursorIcon;
 
 #[derive(PartialEq)]
 pub enum ListViewMode {
     Tracks,
     Playlists,
+    Catalog,  // Tracks outside the user's library, played on their own and offering the track actions
+    Contexts, // Albums and artists, played from the start
 }
 
 fn render_default_square(ui: &mut Ui, rect: egui::Rect) {
@@ -46,75 +52,572 @@ pub fn render_square_with_image(ui: &mut Ui, size: f32, image_url: &str) {
     }
 }
 
-// Add a new parameter to pass playlist_id
-pub fn show_list_view(ui: &mut Ui, tracks: &[(usize, &(String, String, String, String))], mode: ListViewMode, playlist_id: Option<&str>, user_id: &str) {
-    for (original_index, (track, artists, image_url, uri_or_id)) in tracks {
-        let row_response = ui.horizontal(|ui| {
-            render_square_with_image(ui, 40.0, image_url);
-            
-            ui.vertical(|ui| {
-                ui.horizontal(|ui| {
-                    ui.add(egui::Label::new(
-                        egui::RichText::new(track)
-                            .size(16.0)
-                            .strong()
-                            .color(ui.visuals().strong_text_color())
-                            .text_style(egui::TextStyle::Body)
-                    ).wrap());
-                });
-                
-                if mode != ListViewMode::Playlists {
-                    ui.add(egui::Label::new(
-                        egui::RichText::new(artists)
-                            .size(14.0)
-                            .color(ui.visuals().weak_text_color())
-                    ).wrap());
-                }
-            });
-        }).response;
-
-        // Make the row clickable
-        if row_response.interact(egui::Sense::click()).on_hover_cursor(CursorIcon::PointingHand).clicked() {
-            match mode {
-                ListViewMode::Tracks => {
-                    let uri = uri_or_id.clone();
-                    let context_uri = if let Some(id) = playlist_id {
-                        format!("spotify:playlist:{}", id)
-                    } else {
-                        format!("spotify:user:{}:collection", user_id)
-                    };
-                    let position = *original_index;
-                    wasm_bindgen_futures::spawn_local(async move {
-                        crate::api_request::track_status::play_track_with_context(uri, context_uri, position).await;
-                    });
+const LIST_ROW_HEIGHT: f32 = 52.0; // Rows share one height so only the visible ones need laying out
+const GRID_ROW_HEIGHT: f32 = 100.0;
+const LIKED_COLOR: egui::Color32 = egui::Color32::from_rgb(30, 215, 96); // Spotify green
+
+// Tracks dragged out of a track view. Rows dragged out of a playlist remember where they came from,
+// so dropping one back into the same playlist moves it instead of adding a duplicate
+pub struct TrackDrag {
+    pub uris: Vec<String>,
+    pub label: String, // Shown next to the pointer
+    pub source: Option<(String, usize)>, // Playlist ID and position
+}
+
+// What a track view shows and where, shared by all of its rows or cells
+pub struct ListContext<'a> {
+    pub mode: ListViewMode,
+    pub playlist_id: Option<&'a str>, // The playlist shown, if any
+    pub user_id: &'a str,
+    pub reorderable: bool, // Rows take drops at a position and move within the playlist
+    pub selection: &'a mut TrackSelection,
+}
+
+// Scrollable list that only lays out the rows in view; the optional footer (e.g. Load More) sits after the last row.
+// Each track comes with its playlist position, which moves and drops are sent as, so reorderable lists
+// must show the whole playlist in order.
+// Returns the vertical scroll offset so callers can remember it
+pub fn show_list_view(ui: &mut Ui, tracks: &[(usize, &TrackInfo)], list: &mut ListContext, scroll_offset: Option<f32>, footer: Option<&dyn Fn(&mut Ui)>) -> f32 {
+    let mut scroll_area = egui::ScrollArea::vertical().auto_shrink([false, false]);
+    if let Some(offset) = scroll_offset {
+        scroll_area = scroll_area.vertical_scroll_offset(offset);
+    }
+
+    let total_rows = tracks.len() + footer.is_some() as usize;
+    scroll_area.show_rows(ui, LIST_ROW_HEIGHT, total_rows, |ui, row_range| {
+        for row in row_range {
+            match tracks.get(row) {
+                Some((original_index, track)) => show_list_row(ui, *original_index, track, list, tracks),
+                None => {
+                    if let Some(footer) = footer {
+                        ui.allocate_ui(egui::vec2(ui.available_width(), LIST_ROW_HEIGHT), |ui| {
+                            ui.add_space(8.0);
+                            footer(ui);
+                        });
+                    }
                 }
-                ListViewMode::Playlists => {
-                    let id = uri_or_id.clone();
-                    let token = web_sys::window()
-                        .and_then(|window| window.local_storage().ok().flatten())
-                        .and_then(|storage| storage.get_item("spotify_token").ok().flatten())
-                        .unwrap_or_default();
-                    
+            }
+        }
+    }).state.offset.y
+}
+
+fn show_list_row(ui: &mut Ui, original_index: usize, track: &TrackInfo, list: &mut ListContext, tracks: &[(usize, &TrackInfo)]) {
+    let (mode, playlist_id) = (&list.mode, list.playlist_id);
+    let is_track = matches!(mode, ListViewMode::Tracks | ListViewMode::Catalog);
+    let sense = if is_track { egui::Sense::click_and_drag() } else { egui::Sense::click() };
+    let (rect, row_response) = ui.allocate_exact_size(egui::vec2(ui.available_width(), LIST_ROW_HEIGHT), sense);
+    if list.selection.contains(&track.uri) {
+        ui.painter().rect_filled(rect, 0.0, ui.visuals().selection.bg_fill.gamma_multiply(0.4));
+    }
+    // Track rows keep room on the right for the heart and their actions menu
+    let actions_width = match mode {
+        ListViewMode::Tracks | ListViewMode::Catalog => 64.0,
+        ListViewMode::Playlists | ListViewMode::Contexts => 0.0,
+    };
+    let (content_rect, actions_rect) = if actions_width > 0.0 {
+        let (content_rect, actions_rect) = rect.split_left_right_at_x(rect.right() - actions_width);
+        (content_rect, Some(actions_rect))
+    } else {
+        (rect, None)
+    };
+    let content_rect = match playlist_id.filter(|_| list.reorderable) {
+        Some(playlist_id) => {
+            let (handle_rect, content_rect) = content_rect.split_left_right_at_x(content_rect.left() + 20.0);
+            show_drag_handle(ui, handle_rect, track, playlist_id, original_index);
+            content_rect
+        }
+        None => content_rect,
+    };
+    let mut row_ui = ui.new_child(
+        egui::UiBuilder::new()
+            .max_rect(content_rect)
+            .layout(egui::Layout::left_to_right(egui::Align::Center))
+    );
+    render_square_with_image(&mut row_ui, 40.0, &track.image_url);
+    row_ui.vertical(|ui| {
+        ui.add(egui::Label::new(
+            egui::RichText::new(&track.name)
+                .size(16.0)
+                .strong()
+                .color(ui.visuals().strong_text_color())
+                .text_style(egui::TextStyle::Body)
+        ).truncate());
+
+        if *mode != ListViewMode::Playlists {
+            ui.add(egui::Label::new(
+                egui::RichText::new(&track.artists)
+                    .size(14.0)
+                    .color(ui.visuals().weak_text_color())
+            ).truncate());
+        }
+    });
+
+    if let Some(actions_rect) = actions_rect {
+        let mut actions_ui = ui.new_child(
+            egui::UiBuilder::new()
+                .max_rect(actions_rect)
+                .layout(egui::Layout::right_to_left(egui::Align::Center))
+        );
+        actions_ui.menu_button(egui_phosphor::bold::DOTS_THREE, |ui| {
+            show_track_actions(ui, track, playlist_id);
+        });
+        // Rows without a playlist are Liked Songs, where every track is liked
+        show_like_button(&mut actions_ui, track, *mode == ListViewMode::Tracks && playlist_id.is_none());
+    }
+
+    // Separator along the bottom edge, since rows can't grow to fit one
+    ui.painter().hline(
+        rect.x_range(),
+        rect.bottom(),
+        ui.visuals().widgets.noninteractive.bg_stroke,
+    );
+
+    start_track_drag(&row_response, track, playlist_id, original_index, list.selection, tracks);
+    if let Some(playlist_id) = playlist_id.filter(|_| list.reorderable) {
+        handle_row_drop(ui, &row_response, playlist_id, original_index);
+    }
+
+    show_context_menu(&row_response, mode, track, playlist_id);
+
+    // Make the row clickable; shift and ctrl clicks select instead of playing
+    if row_response.on_hover_cursor(CursorIcon::PointingHand).clicked() {
+        let modifiers = ui.input(|i| i.modifiers);
+        if !is_track || !list.selection.handle_click(modifiers, tracks, &track.uri) {
+            open_item(&list.mode, track, original_index, playlist_id, list.user_id);
+        }
+    }
+}
+
+// Grip on reorderable rows. The whole row can be dragged too, but the handle always moves just its
+// own row, even when it's part of a larger selection
+fn show_drag_handle(ui: &mut Ui, rect: egui::Rect, track: &TrackInfo, playlist_id: &str, position: usize) {
+    // Keyed by position rather than layout order, so the drag survives rows scrolling in and out
+    let handle = ui.interact(rect, egui::Id::new(("playlist_row_handle", playlist_id, position)), egui::Sense::drag())
+        .on_hover_text("Drag to reorder")
+        .on_hover_cursor(CursorIcon::Grab);
+    start_track_drag(&handle, track, Some(playlist_id), position, &TrackSelection::default(), &[]);
+    let color = if handle.hovered() || handle.dragged() {
+        ui.visuals().strong_text_color()
+    } else {
+        ui.visuals().weak_text_color()
+    };
+    ui.painter().text(
+        rect.center(),
+        egui::Align2::CENTER_CENTER,
+        egui_phosphor::bold::DOTS_SIX_VERTICAL,
+        egui::FontId::proportional(16.0),
+        color,
+    );
+}
+
+// Starts dragging a track out of its row or cell. Only tracks that can go in a playlist or the queue are draggable
+// Dragging a selected row takes the whole selection along, in the order shown
+pub fn start_track_drag(response: &egui::Response, track: &TrackInfo, playlist_id: Option<&str>, index: usize, selection: &TrackSelection, tracks: &[(usize, &TrackInfo)]) {
+    if !response.drag_started() {
+        return;
+    }
+    let draggable = |track: &TrackInfo| track.uri.starts_with("spotify:track:") || track.uri.starts_with("spotify:episode:");
+    let uris: Vec<String> = if selection.len() > 1 && selection.contains(&track.uri) {
+        selection.selected(tracks.iter().map(|(_, track)| *track))
+            .into_iter()
+            .filter(|track| draggable(track))
+            .map(|track| track.uri.clone())
+            .collect()
+    } else if draggable(track) {
+        vec![track.uri.clone()]
+    } else {
+        Vec::new()
+    };
+    if !uris.is_empty() {
+        response.dnd_set_drag_payload(TrackDrag {
+            uris,
+            label: format!("{} – {}", track.name, track.artists),
+            source: playlist_id.map(|id| (id.to_string(), index)),
+        });
+    }
+}
+
+// Shows where dragged tracks would land in a playlist and puts them there on release. Dropping on the
+// top half of a row inserts before it, the bottom half after it. A row dragged within its own playlist moves
+fn handle_row_drop(ui: &mut Ui, row_response: &egui::Response, playlist_id: &str, index: usize) {
+    let rect = row_response.rect;
+    let below = ui.input(|i| i.pointer.interact_pos()).is_some_and(|pos| pos.y > rect.center().y);
+    let insert_before = if below { index + 1 } else { index };
+
+    if row_response.dnd_hover_payload::<TrackDrag>().is_some() {
+        let y = if below { rect.bottom() } else { rect.top() };
+        ui.painter().hline(rect.x_range(), y, ui.visuals().selection.stroke);
+    }
+    if let Some(payload) = row_response.dnd_release_payload::<TrackDrag>() {
+        let Some(token) = get_token() else { return };
+        let playlist_id = playlist_id.to_string();
+        match &payload.source {
+            // Only single rows move; a selection dropped on its own playlist would just duplicate it
+            Some((source_id, from)) if *source_id == playlist_id => {
+                if payload.uris.len() == 1 {
+                    let from = *from;
                     wasm_bindgen_futures::spawn_local(async move {
-                        crate::api_request::playlist_tracks::fetch_playlist_tracks(id, token).await;
+                        crate::api_request::playlist_edit::reorder_playlist_track(token, playlist_id, from, insert_before).await;
                     });
                 }
             }
+            _ => {
+                let uris = payload.uris.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    crate::api_request::playlist_edit::add_tracks_to_playlist(token, playlist_id, uris, Some(insert_before)).await;
+                });
+            }
+        }
+    }
+}
+
+// Lets tracks be dropped anywhere in a playlist view, appending them. Rows that take a drop at a
+// position are checked first, so this only sees drops that missed them
+pub fn accept_playlist_drop(ui: &mut Ui, playlist_id: &str) {
+    let response = ui.interact(ui.min_rect(), ui.id().with("track_drop"), egui::Sense::hover());
+    if let Some(payload) = response.dnd_hover_payload::<TrackDrag>() {
+        // Rows dragged within their own playlist are reordered by the rows, not appended again
+        if payload.source.as_ref().is_some_and(|(id, _)| id == playlist_id) {
+            return;
+        }
+        ui.painter().rect_stroke(response.rect, 4.0, ui.visuals().selection.stroke);
+    }
+    if let Some(payload) = response.dnd_release_payload::<TrackDrag>() {
+        if payload.source.as_ref().is_some_and(|(id, _)| id == playlist_id) {
+            return;
+        }
+        if let Some(token) = get_token() {
+            let playlist_id = playlist_id.to_string();
+            let uris = payload.uris.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                crate::api_request::playlist_edit::add_tracks_to_playlist(token, playlist_id, uris, None).await;
+            });
+        }
+    }
+}
+
+// Lets tracks be dropped on the player to add them to the queue, in the order they were dragged
+pub fn accept_queue_drop(ui: &mut Ui) {
+    let response = ui.interact(ui.min_rect(), ui.id().with("queue_drop"), egui::Sense::hover());
+    if response.dnd_hover_payload::<TrackDrag>().is_some() {
+        ui.painter().rect_stroke(response.rect, 4.0, ui.visuals().selection.stroke);
+        ui.painter().text(
+            response.rect.center_top() + egui::vec2(0.0, 12.0),
+            egui::Align2::CENTER_TOP,
+            format!("{} Drop to add to queue", egui_phosphor::bold::QUEUE),
+            egui::FontId::proportional(14.0),
+            ui.visuals().strong_text_color(),
+        );
+    }
+    if let Some(payload) = response.dnd_release_payload::<TrackDrag>() {
+        let uris = payload.uris.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            for uri in uris {
+                crate::api_request::track_status::add_to_queue(uri).await;
+            }
+        });
+    }
+}
+
+// Names what's being dragged next to the pointer; called once per frame
+pub fn show_track_drag_preview(ctx: &egui::Context) {
+    let Some(payload) = egui::DragAndDrop::payload::<TrackDrag>(ctx) else { return };
+    let Some(pos) = ctx.pointer_hover_pos() else { return };
+    let text = if payload.uris.len() == 1 {
+        payload.label.clone()
+    } else {
+        format!("{} tracks", payload.uris.len())
+    };
+    let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Tooltip, egui::Id::new("track_drag_preview")));
+    let galley = painter.layout_no_wrap(text, egui::FontId::proportional(14.0), ctx.style().visuals.strong_text_color());
+    let rect = egui::Rect::from_min_size(pos + egui::vec2(16.0, 8.0), galley.size()).expand(6.0);
+    painter.rect_filled(rect, 4.0, ctx.style().visuals.window_fill);
+    painter.rect_stroke(rect, 4.0, ctx.style().visuals.window_stroke);
+    painter.galley(rect.min + egui::vec2(6.0, 6.0), galley, egui::Color32::WHITE);
+}
+
+// What clicking a row or grid cell does in each mode
+fn open_item(mode: &ListViewMode, track: &TrackInfo, original_index: usize, playlist_id: Option<&str>, user_id: &str) {
+    match mode {
+        ListViewMode::Tracks => {
+            let uri = track.uri.clone();
+            let context_uri = if let Some(id) = playlist_id {
+                format!("spotify:playlist:{}", id)
+            } else {
+                format!("spotify:user:{}:collection", user_id)
+            };
+            wasm_bindgen_futures::spawn_local(async move {
+                crate::api_request::track_status::play_track_with_context(uri, context_uri, original_index).await;
+            });
+        }
+        ListViewMode::Playlists => {
+            let id = track.uri.clone();
+            let token = web_sys::window()
+                .and_then(|window| window.local_storage().ok().flatten())
+                .and_then(|storage| storage.get_item("spotify_token").ok().flatten())
+                .unwrap_or_default();
+            
+            wasm_bindgen_futures::spawn_local(async move {
+                crate::api_request::playlist_tracks::fetch_playlist_tracks(id, token).await;
+            });
+        }
+        ListViewMode::Catalog => {
+            let uri = track.uri.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                crate::api_request::track_status::play_uris(vec![uri]).await;
+            });
+        }
+        ListViewMode::Contexts => {
+            let uri = track.uri.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                crate::api_request::track_status::play_context(uri).await;
+            });
+        }
+    }
+}
+
+// Heart that saves a track to or removes it from Liked Songs. Tracks whose status isn't known yet
+// get a dimmed heart until the batched lookup answers
+pub fn show_like_button(ui: &mut Ui, track: &TrackInfo, in_liked_songs: bool) {
+    // Local files and podcast episodes can't be saved to Liked Songs
+    if !track.uri.starts_with("spotify:track:") {
+        return;
+    }
+    let liked = if in_liked_songs {
+        Some(true)
+    } else {
+        APP_STATE.lock().unwrap().track_liked(track.id())
+    };
+
+    let color = match liked {
+        Some(true) => LIKED_COLOR,
+        Some(false) => ui.visuals().text_color(),
+        None => ui.visuals().weak_text_color(),
+    };
+    let hover_text = if liked == Some(true) { "Remove from Liked Songs" } else { "Save to Liked Songs" };
+    if ui.add(egui::Button::new(egui::RichText::new(egui_phosphor::bold::HEART).color(color)).frame(false))
+        .on_hover_text(hover_text)
+        .on_hover_cursor(CursorIcon::PointingHand)
+        .clicked() {
+        if let Some(token) = get_token() {
+            let track = track.clone();
+            let liked = liked != Some(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                crate::api_request::library::set_track_liked(token, track, liked).await;
+            });
+        }
+    }
+}
+
+// Submenu listing the playlists the user can add to; picking one appends the tracks to it
+pub fn show_playlist_picker(ui: &mut Ui, token: &str, uris: &[String]) {
+    let state = APP_STATE.lock().unwrap();
+    let playlists = state.playlists.clone();
+    let user_id = state.user_id.clone().unwrap_or_default();
+    drop(state);
+    if playlists.is_empty() {
+        ui.label("Your playlists haven't been loaded yet.");
+        if ui.button("Load playlists").on_hover_cursor(CursorIcon::PointingHand).clicked() {
+            let token = token.to_string();
+            wasm_bindgen_futures::spawn_local(async move {
+                crate::api_request::playlists::fetch_playlists(token).await;
+            });
+        }
+        return;
+    }
+    egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+        for playlist in playlists.into_iter().filter(|p| p.is_editable_by(&user_id)) {
+            if ui.button(&playlist.name).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+                let token = token.to_string();
+                let uris = uris.to_vec();
+                let id = playlist.id;
+                wasm_bindgen_futures::spawn_local(async move {
+                    crate::api_request::playlist_edit::add_tracks_to_playlist(token, id, uris, None).await;
+                });
+                ui.close_menu();
+            }
+        }
+    });
+}
+
+// Play, queue, like and add-to-playlist actions for a single track, plus removal when it's shown in a playlist.
+// Used by the actions button and the right-click menu of every track view
+pub fn show_track_actions(ui: &mut Ui, track: &TrackInfo, playlist_id: Option<&str>) {
+    let token = get_token().unwrap_or_default();
+
+    if ui.button(format!("{} Play", egui_phosphor::bold::PLAY)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+        let uri = track.uri.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            crate::api_request::track_status::play_uris(vec![uri]).await;
+        });
+        ui.close_menu();
+    }
+    if ui.button(format!("{} Add to queue", egui_phosphor::bold::QUEUE)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+        let uri = track.uri.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            crate::api_request::track_status::add_to_queue(uri).await;
+        });
+        ui.close_menu();
+    }
+    let liked = APP_STATE.lock().unwrap().track_liked(track.id()).unwrap_or(false);
+    let like_label = if liked { "Remove from Liked Songs" } else { "Save to Liked Songs" };
+    if ui.button(format!("{} {}", egui_phosphor::bold::HEART, like_label)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+        let token = token.clone();
+        let track = track.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            crate::api_request::library::set_track_liked(token, track, !liked).await;
+        });
+        ui.close_menu();
+    }
+    ui.menu_button(format!("{} Add to playlist", egui_phosphor::bold::LIST_PLUS), |ui| {
+        show_playlist_picker(ui, &token, std::slice::from_ref(&track.uri));
+    });
+    if let Some(playlist_id) = playlist_id {
+        if ui.button(format!("{} Remove from this playlist", egui_phosphor::bold::TRASH)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+            let playlist_id = playlist_id.to_string();
+            let uris = vec![track.uri.clone()];
+            wasm_bindgen_futures::spawn_local(async move {
+                crate::api_request::playlist_edit::remove_tracks_from_playlist(token, playlist_id, uris).await;
+            });
+            ui.close_menu();
+        }
+    }
+
+    ui.separator();
+    // Tracks cached before album and artist URIs were stored can't link to them
+    if !track.album_uri.is_empty() && ui.button(format!("{} Go to album", egui_phosphor::bold::VINYL_RECORD)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+        open_spotify_link(&track.album_uri);
+        ui.close_menu();
+    }
+    if !track.artist_uri.is_empty() && ui.button(format!("{} Go to artist", egui_phosphor::bold::USER)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+        open_spotify_link(&track.artist_uri);
+        ui.close_menu();
+    }
+    if ui.button(format!("{} Copy Spotify link", egui_phosphor::bold::LINK)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+        ui.ctx().copy_text(spotify_link(&track.uri));
+        ui.close_menu();
+    }
+}
+
+// Right-click actions for a playlist row or cell. Playlists pose as tracks with their ID in uri
+pub fn show_playlist_actions(ui: &mut Ui, playlist: &TrackInfo) {
+    let token = get_token().unwrap_or_default();
+    let playlist_id = playlist.uri.clone();
+    let context_uri = format!("spotify:playlist:{}", playlist_id);
+
+    if ui.button(format!("{} Open", egui_phosphor::bold::LIST)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+        let token = token.clone();
+        let playlist_id = playlist_id.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            crate::api_request::playlist_tracks::fetch_playlist_tracks(playlist_id, token).await;
+        });
+        ui.close_menu();
+    }
+    if ui.button(format!("{} Play", egui_phosphor::bold::PLAY)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+        let context_uri = context_uri.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            crate::api_request::track_status::play_context(context_uri).await;
+        });
+        ui.close_menu();
+    }
+    if ui.button(format!("{} Shuffle play", egui_phosphor::bold::SHUFFLE)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+        let context_uri = context_uri.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            crate::api_request::track_status::set_shuffle(true).await;
+            crate::api_request::track_status::play_context(context_uri).await;
+        });
+        ui.close_menu();
+    }
+    if ui.button(format!("{} Export as CSV", egui_phosphor::bold::DOWNLOAD_SIMPLE)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+        let token = token.clone();
+        let playlist_id = playlist_id.clone();
+        let name = playlist.name.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            crate::api_request::playlist_tracks::export_playlist(playlist_id, name, token).await;
+        });
+        ui.close_menu();
+    }
+    if ui.button(format!("{} Duplicate", egui_phosphor::bold::COPY)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+        let token = token.clone();
+        let playlist_id = playlist_id.clone();
+        let name = playlist.name.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            crate::api_request::playlist_edit::duplicate_playlist(token, playlist_id, name).await;
+        });
+        ui.close_menu();
+    }
+    if ui.button(format!("{} Open in new window", egui_phosphor::bold::ARROW_SQUARE_OUT))
+        .on_hover_text("Opens the playlist in the Spotify web player")
+        .on_hover_cursor(CursorIcon::PointingHand)
+        .clicked() {
+        open_spotify_link(&context_uri);
+        ui.close_menu();
+    }
+    ui.separator();
+    if ui.button(format!("{} Copy Spotify link", egui_phosphor::bold::LINK)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+        ui.ctx().copy_text(spotify_link(&context_uri));
+        ui.close_menu();
+    }
+
+    // Playlists in the user's library can be edited when they own them, and removed either way
+    let state = APP_STATE.lock().unwrap();
+    let library_playlist = state.playlists.iter().find(|p| p.id == playlist_id).cloned();
+    let user_id = state.user_id.clone().unwrap_or_default();
+    drop(state);
+    let Some(library_playlist) = library_playlist else {
+        return;
+    };
+    let owned = library_playlist.is_owned_by(&user_id);
+    ui.separator();
+    if owned && ui.button(format!("{} Edit details", egui_phosphor::bold::PENCIL_SIMPLE)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+        APP_STATE.lock().unwrap().playlist_draft = Some(PlaylistDraft::edit(&library_playlist));
+        ui.close_menu();
+    }
+    let remove_label = if owned { "Delete" } else { "Remove from library" };
+    ui.menu_button(format!("{} {}", egui_phosphor::bold::TRASH, remove_label), |ui| {
+        ui.label(format!("{} \"{}\"?", remove_label, library_playlist.name));
+        if ui.button(egui::RichText::new(remove_label).color(ui.visuals().error_fg_color)).on_hover_cursor(CursorIcon::PointingHand).clicked() {
+            wasm_bindgen_futures::spawn_local(async move {
+                crate::api_request::playlist_edit::unfollow_playlist(token, playlist_id).await;
+            });
+            ui.close_menu();
         }
-        
-        ui.add_space(4.0);
-        ui.separator();
-        ui.add_space(4.0);
+    });
+}
+
+// Opens a Spotify URI on open.spotify.com in a new browser tab
+fn open_spotify_link(uri: &str) {
+    if let Some(window) = web_sys::window() {
+        let _ = window.open_with_url_and_target(&spotify_link(uri), "_blank");
     }
 }
 
-// Update grid view similarly
-pub fn show_grid_view(ui: &mut Ui, tracks: &[(usize, &(String, String, String, String))], total_tracks: Option<i32>, saved_tracks_len: usize, loaded_tracks_count: i32, mode: ListViewMode, playlist_id: Option<&str>, user_id: &str) {
+// Right-click menu for a row or cell of any track view
+pub fn show_context_menu(response: &egui::Response, mode: &ListViewMode, track: &TrackInfo, playlist_id: Option<&str>) {
+    match mode {
+        ListViewMode::Tracks | ListViewMode::Catalog => {
+            response.context_menu(|ui| show_track_actions(ui, track, playlist_id));
+        }
+        ListViewMode::Playlists => {
+            response.context_menu(|ui| show_playlist_actions(ui, track));
+        }
+        ListViewMode::Contexts => {}
+    }
+}
+
+// Update grid view similarly; returns the vertical scroll offset so callers can remember it
+pub fn show_grid_view(ui: &mut Ui, tracks: &[(usize, &TrackInfo)], total_tracks: Option<i32>, saved_tracks_len: usize, loaded_tracks_count: i32, list: &mut ListContext, scroll_offset: Option<f32>) -> f32 {
+    let (mode, playlist_id, user_id) = (&list.mode, list.playlist_id, list.user_id);
+    let is_track = matches!(mode, ListViewMode::Tracks | ListViewMode::Catalog);
     let available_width = ui.available_width();
     let column_width = (available_width / 3.0).max(100.0) - 10.0; // Add padding
     
     egui::ScrollArea::horizontal().show(ui, |ui| {
-        TableBuilder::new(ui)
+        let mut table = TableBuilder::new(ui);
+        if let Some(offset) = scroll_offset {
+            table = table.vertical_scroll_offset(offset);
+        }
+        table
             .striped(true)
             .resizable(false)
             .cell_layout(egui::Layout::top_down_justified(egui::Align::Center))
@@ -122,109 +625,100 @@ pub fn show_grid_view(ui: &mut Ui, tracks: &[(usize, &(String, String, String, S
             .column(Column::exact(column_width))
             .column(Column::exact(column_width))
             .vscroll(true)
-            .body(|mut body| {
-                let rows = (tracks.len() + 2) / 3;
-                for row_idx in 0..rows {
-                    body.row(100.0, |mut row| {
-                        for col in 0..3 {
-                            let idx = row_idx * 3 + col;
-                            if let Some((original_index, (track, artists, image_url, uri_or_id))) = tracks.get(idx) {
-                                row.col(|ui| {
-                                    ui.scope(|ui| {
-                                        draw_vlines(ui, 100.0, col > 0, |ui| {
-                                            ui.horizontal(|ui| {
-                                                render_square_with_image(ui, 80.0, image_url);
-                                                ui.add_space(8.0);
-                                                ui.vertical(|ui| {
+            .body(|body| {
+                let rows = tracks.len().div_ceil(3);
+                let show_load_more = match total_tracks {
+                    Some(total) => tracks.len() >= saved_tracks_len && loaded_tracks_count < total,
+                    None => false,
+                };
+                // Only the rows in view are laid out; the Load More row comes after the last one
+                let row_heights = std::iter::repeat_n(GRID_ROW_HEIGHT, rows)
+                    .chain(show_load_more.then_some(50.0));
+                body.heterogeneous_rows(row_heights, |mut row| {
+                    let row_idx = row.index();
+                    if row_idx == rows {
+                        // Use all three columns for the button
+                        row.col(|_| {});  // Empty first column
+                        row.col(|ui| {
+                            // Center the button in the middle column
+                            if ui.button("Load More").on_hover_cursor(CursorIcon::PointingHand).clicked() {
+                                let token = web_sys::window()
+                                    .and_then(|window| window.local_storage().ok().flatten())
+                                    .and_then(|storage| storage.get_item("spotify_token").ok().flatten())
+                                    .unwrap_or_default();
+                                
+                                wasm_bindgen_futures::spawn_local(async move {
+                                    crate::api_request::saved_tracks::load_more_tracks(token, false).await;
+                                });
+                            }
+                        });
+                        row.col(|_| {});  // Empty third column
+                        return;
+                    }
+
+                    for col in 0..3 {
+                        let idx = row_idx * 3 + col;
+                        if let Some((original_index, track)) = tracks.get(idx) {
+                            row.col(|ui| {
+                                if list.selection.contains(&track.uri) {
+                                    ui.painter().rect_filled(ui.max_rect(), 0.0, ui.visuals().selection.bg_fill.gamma_multiply(0.4));
+                                }
+                                ui.scope(|ui| {
+                                    draw_vlines(ui, 100.0, col > 0, |ui| {
+                                        ui.horizontal(|ui| {
+                                            render_square_with_image(ui, 80.0, &track.image_url);
+                                            ui.add_space(8.0);
+                                            ui.vertical(|ui| {
+                                                ui.add(
+                                                    egui::Label::new(
+                                                        egui::RichText::new(&track.name)
+                                                            .size(16.0)
+                                                            .strong()
+                                                            .color(ui.visuals().strong_text_color())
+                                                            .text_style(egui::TextStyle::Body)
+                                                    ).wrap()
+                                                );
+                                                if *mode != ListViewMode::Playlists {
                                                     ui.add(
                                                         egui::Label::new(
-                                                            egui::RichText::new(track)
-                                                                .size(16.0)
-                                                                .strong()
-                                                                .color(ui.visuals().strong_text_color())
-                                                                .text_style(egui::TextStyle::Body)
+                                                            egui::RichText::new(&track.artists)
+                                                                .size(14.0)
+                                                                .color(ui.visuals().weak_text_color())
                                                         ).wrap()
                                                     );
-                                                    if mode != ListViewMode::Playlists {
-                                                        ui.add(
-                                                            egui::Label::new(
-                                                                egui::RichText::new(artists)
-                                                                    .size(14.0)
-                                                                    .color(ui.visuals().weak_text_color())
-                                                            ).wrap()
-                                                        );
-                                                    }
-                                                });
-                                                
-                                                // Make the cell clickable
-                                                if ui.rect_contains_pointer(ui.min_rect()) {
-                                                    ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
-                                                }
-                                                if ui.rect_contains_pointer(ui.min_rect()) && ui.input(|i| i.pointer.primary_clicked()) {
-                                                    match mode {
-                                                        ListViewMode::Tracks => {
-                                                            let uri = uri_or_id.clone();
-                                                            let context_uri = if let Some(id) = playlist_id {
-                                                                format!("spotify:playlist:{}", id)
-                                                            } else {
-                                                                format!("spotify:user:{}:collection", user_id)
-                                                            };
-                                                            let position = *original_index;
-                                                            wasm_bindgen_futures::spawn_local(async move {
-                                                                crate::api_request::track_status::play_track_with_context(uri, context_uri, position).await;
-                                                            });
-                                                        }
-                                                        ListViewMode::Playlists => {
-                                                            let id = uri_or_id.clone();
-                                                            let token = web_sys::window()
-                                                                .and_then(|window| window.local_storage().ok().flatten())
-                                                                .and_then(|storage| storage.get_item("spotify_token").ok().flatten())
-                                                                .unwrap_or_default();
-                                                            
-                                                            wasm_bindgen_futures::spawn_local(async move {
-                                                                crate::api_request::playlist_tracks::fetch_playlist_tracks(id, token).await;
-                                                            });
-                                                        }
-                                                    }
                                                 }
                                             });
+                                            
+                                            // Cells are clicked, dragged and right-clicked as a whole, like list rows
+                                            let cell = ui.interact(ui.min_rect(), egui::Id::new(("grid_cell", playlist_id, *original_index)), egui::Sense::click_and_drag());
+                                            if is_track {
+                                                start_track_drag(&cell, track, playlist_id, *original_index, list.selection, tracks);
+                                            }
+                                            show_context_menu(&cell, mode, track, playlist_id);
+
+                                            // Make the cell clickable
+                                            if cell.on_hover_cursor(CursorIcon::PointingHand).clicked() {
+                                                let modifiers = ui.input(|i| i.modifiers);
+                                                if !is_track || !list.selection.handle_click(modifiers, tracks, &track.uri) {
+                                                    open_item(mode, track, *original_index, playlist_id, user_id);
+                                                }
+                                            }
                                         });
                                     });
                                 });
-                            } else {
-                                row.col(|ui| {
-                                    draw_vlines(ui, 100.0, col > 0, |_| {});
-                                });
-                            }
-                        }
-                    });
-                }
-
-                // Add Load More button only after the last row if we have more tracks to load
-                if let Some(total) = total_tracks {
-                    if tracks.len() >= saved_tracks_len && loaded_tracks_count < total {
-                        body.row(50.0, |mut row| {
-                            // Use all three columns for the button
-                            row.col(|_| {});  // Empty first column
+                            });
+                        } else {
                             row.col(|ui| {
-                                // Center the button in the middle column
-                                if ui.button("Load More").on_hover_cursor(CursorIcon::PointingHand).clicked() {
-                                    let token = web_sys::window()
-                                        .and_then(|window| window.local_storage().ok().flatten())
-                                        .and_then(|storage| storage.get_item("spotify_token").ok().flatten())
-                                        .unwrap_or_default();
-                                    
-                                    wasm_bindgen_futures::spawn_local(async move {
-                                        crate::api_request::saved_tracks::load_more_tracks(token, false).await;
-                                    });
-                                }
+                                draw_vlines(ui, 100.0, col > 0, |_| {});
                             });
-                            row.col(|_| {});  // Empty third column
-                        });
+                        }
                     }
-                }
-            });
-    });
+                });
+            })
+            .state
+            .offset
+            .y
+    }).inner
 }
 
 fn draw_vlines<R>(ui: &mut Ui, _height: f32, draw_left: bool, next: impl FnOnce(&mut Ui) -> R) {
@@ -8,19 +8,47 @@ use wasm_bindgen::JsCast;  // Add JsCast trait for dyn_ref
 use crate::api_request::playlists::fetch_playlists;
 use crate::ui::playlist_tracks::show_playlist_tracks_windows;
 use egui::CursorIcon; // new import
+use super::dock::DockWorkspace;
+use super::layouts::show_layouts_section;
+use super::session::{restore_session, SessionSaver};
 
-#[derive(Default)]
 pub struct SpotifyApp {
     pub show_player: bool, // new field
     pub sidebar_open: bool, // new field
+    pub dock: DockWorkspace, // Tab layout used in dock mode
+    session: SessionSaver, // Persists open windows, search and scroll state across reloads
+}
+
+// Loads the dock tree, then reopens the windows from the last session
+fn restore_workspace() -> DockWorkspace {
+    let mut dock = DockWorkspace::restore();
+    restore_session(&mut APP_STATE.lock().unwrap(), &mut dock);
+    dock
+}
+
+impl Default for SpotifyApp {
+    fn default() -> Self {
+        Self {
+            show_player: false,
+            sidebar_open: false,
+            dock: restore_workspace(),
+            session: SessionSaver::default(),
+        }
+    }
 }
 
 impl eframe::App for SpotifyApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let logged_in;
+        let dock_mode;
         {
             let mut state = APP_STATE.lock().unwrap();
             // Sync sidebar state from SpotifyApp to AppState
             state.sidebar_open = self.sidebar_open;
+            logged_in = state.username.is_some();
+            dock_mode = state.dock_mode;
+            // The player may have been opened as a dock tab
+            self.show_player |= state.player_window_open;
             
             egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
                 ui.horizontal(|ui| {
@@ -37,7 +65,10 @@ impl eframe::App for SpotifyApp {
                                         if let Some(storage) = local_storage {
                                             let _ = storage.remove_item("spotify_token");
                                             state.username = None;
-                                            state.saved_tracks.clear();
+                                            state.saved_tracks = Default::default();
+                                            state.liked_tracks.clear();
+                                            state.requested_liked_checks.clear();
+                                            state.playlist_draft = None;
                                             state.show_tracks = false;
                                         }
                                     }
@@ -72,6 +103,9 @@ impl eframe::App for SpotifyApp {
                                     fetch_playlists(token).await;
                                 });
                             }
+                            if ui.button("Search Spotify").clicked() {
+                                state.search_window_open = true;
+                            }
                             if ui.button("Create Collage").clicked() {
                                 state.collage_window_open = true;
                             }
@@ -116,6 +150,9 @@ impl eframe::App for SpotifyApp {
                                     }
                                 });
                             }
+
+                            ui.separator();
+                            show_layouts_section(ui, &mut state, &mut self.dock);
                             
                             ui.with_layout(egui::Layout::bottom_up(egui::Align::Center), |ui| {
                                 let label = egui::Label::new("🏠")
@@ -133,35 +170,41 @@ impl eframe::App for SpotifyApp {
                     });
             }
 
-            egui::CentralPanel::default().show(ctx, |ui| {
-                // Only show sidebar toggle when logged in
-                if state.username.is_some() {
-                    if ui.button(if self.sidebar_open { "⬅" } else { "➡" }).clicked() {
-                        self.sidebar_open = !self.sidebar_open;
-                    }
-                }
+        }
 
-                if let Some(_) = &state.username {                 
-                    // Removed buttons from here
-                } else {
-                    ui.vertical_centered(|ui| {
-                        ui.add_space(100.0); // Add some space from the top
-                        if ui.add_sized([200.0, 50.0], egui::Button::new("Connect with Spotify"))
-                            .on_hover_cursor(CursorIcon::PointingHand)
-                            .clicked() {
-                            loginWithSpotify();
-                        }
-                    });
+        egui::CentralPanel::default().show(ctx, |ui| {
+            // Only show sidebar toggle when logged in
+            if logged_in && ui.button(if self.sidebar_open { "⬅" } else { "➡" }).clicked() {
+                self.sidebar_open = !self.sidebar_open;
+            }
+
+            if logged_in {
+                if dock_mode {
+                    self.dock.show(ui);
                 }
-            });
-        }
+            } else {
+                ui.vertical_centered(|ui| {
+                    ui.add_space(100.0); // Add some space from the top
+                    if ui.add_sized([200.0, 50.0], egui::Button::new("Connect with Spotify"))
+                        .on_hover_cursor(CursorIcon::PointingHand)
+                        .clicked() {
+                        loginWithSpotify();
+                    }
+                });
+            }
+        });
 
-        // Show saved tracks window in a separate scope
-        show_saved_tracks_window(ctx);
-        super::settings::show_settings_window(ctx);
-        super::collage::show_collage_window(ctx);
-        super::playlists_window::show_playlists_window(ctx);
-        show_playlist_tracks_windows(ctx); // Call the new function
+        // Floating windows are replaced by dock tabs in dock mode
+        if !dock_mode {
+            show_saved_tracks_window(ctx);
+            super::settings::show_settings_window(ctx);
+            super::collage::show_collage_window(ctx);
+            super::playlists_window::show_playlists_window(ctx);
+            super::search_window::show_search_window(ctx);
+            show_playlist_tracks_windows(ctx); // Call the new function
+        }
+        // The playlist dialog floats above the dock too
+        super::playlists_window::show_playlist_dialog(ctx);
         
         // Check loading state in a separate scope
         let is_loading = {
@@ -173,20 +216,36 @@ impl eframe::App for SpotifyApp {
             ctx.request_repaint();
         }
 
-        if self.show_player {
+        if self.show_player && !dock_mode {
             // call the media player widget here
             super::super::mediaplayer::mediaplayerwidget::show_mediaplayer_window(ctx);
         }
+
+        let session_pending = {
+            let mut state = APP_STATE.lock().unwrap();
+            let library_pending = self.session.save_library(&mut state);
+            self.session.save_if_changed(&state, &self.dock) || library_pending
+        };
+        if session_pending {
+            // Come back once the save interval has passed so the last change isn't lost
+            ctx.request_repaint_after(std::time::Duration::from_secs(1));
+        }
+
+        // Look up the liked status of rows drawn this frame
+        crate::api_request::library::flush_liked_checks();
+        super::tracks_ui::show_track_drag_preview(ctx);
     }
 }
 
 // Add this implementation for SpotifyApp
 impl SpotifyApp {
     pub fn new() -> Self {
-        let state = APP_STATE.lock().unwrap();
+        let sidebar_open = APP_STATE.lock().unwrap().sidebar_open;
         Self {
             show_player: false,
-            sidebar_open: state.sidebar_open,
+            sidebar_open,
+            dock: restore_workspace(),
+            session: SessionSaver::default(),
         }
     }
 }
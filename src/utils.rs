@@ -1,4 +1,6 @@
-use web_sys::console;
+use web_sys::{console, Blob, Url};
+use wasm_bindgen::JsCast;
+use wasm_bindgen::closure::Closure;
 
 pub fn log_error(message: &str) {
     console::error_1(&message.into());
@@ -13,4 +15,63 @@ pub fn clear_token_and_redirect() {
         }
         window.location().set_href("/").unwrap();
     }
-}
\ No newline at end of file
+}
+
+// Triggers a browser download of the given bytes
+pub fn download_file(data: &[u8], filename: &str) {
+    // Create a Blob from the data
+    let array = js_sys::Uint8Array::from(data);
+    let blob_parts = js_sys::Array::new();
+    blob_parts.push(&array);
+    
+    if let Ok(blob) = Blob::new_with_u8_array_sequence(&blob_parts) {
+        if let Ok(url) = Url::create_object_url_with_blob(&blob) {
+            if let Some(window) = web_sys::window() {
+                if let Some(document) = window.document() {
+                    if let Ok(link) = document.create_element("a") {
+                        let link = link.dyn_into::<web_sys::HtmlAnchorElement>().unwrap();
+                        link.set_href(&url);
+                        link.set_download(filename);
+                        link.click();
+                        
+                        // Clean up
+                        let _ = Url::revoke_object_url(&url);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Opens the browser's file picker and reads the chosen file as (name, bytes). Resolves to None when
+// the picker is dismissed, in browsers that report it
+pub async fn pick_file(accept: &str) -> Option<(String, Vec<u8>)> {
+    let document = web_sys::window()?.document()?;
+    let input = document.create_element("input").ok()?.dyn_into::<web_sys::HtmlInputElement>().ok()?;
+    input.set_type("file");
+    input.set_accept(accept);
+
+    let (sender, receiver) = futures::channel::oneshot::channel();
+    let sender = std::rc::Rc::new(std::cell::RefCell::new(Some(sender)));
+    let change_sender = sender.clone();
+    let on_change = Closure::<dyn FnMut()>::new(move || {
+        if let Some(sender) = change_sender.borrow_mut().take() {
+            let _ = sender.send(true);
+        }
+    });
+    let on_cancel = Closure::<dyn FnMut()>::new(move || {
+        if let Some(sender) = sender.borrow_mut().take() {
+            let _ = sender.send(false);
+        }
+    });
+    input.set_onchange(Some(on_change.as_ref().unchecked_ref()));
+    input.set_oncancel(Some(on_cancel.as_ref().unchecked_ref()));
+    input.click();
+
+    if !receiver.await.unwrap_or(false) {
+        return None;
+    }
+    let file = input.files()?.get(0)?;
+    let buffer = wasm_bindgen_futures::JsFuture::from(file.array_buffer()).await.ok()?;
+    Some((file.name(), js_sys::Uint8Array::new(&buffer).to_vec()))
+}